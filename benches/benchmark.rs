@@ -1,7 +1,10 @@
-use std::time::Duration;
+use std::{hint::black_box, time::Duration};
 
 use criterion::{
-    BenchmarkId, Criterion, Throughput, black_box, criterion_group,
+    BenchmarkId,
+    Criterion,
+    Throughput,
+    criterion_group,
     criterion_main,
 };
 
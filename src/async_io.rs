@@ -0,0 +1,482 @@
+// Async counterparts of `HexWriter` and `HexReader`, sharing their
+// buffering. Each adapter implements the tokio and `futures-io` traits
+// behind the feature of the same name.
+
+use core::{
+    mem::MaybeUninit,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+use std::io;
+
+#[cfg(feature = "futures-io")]
+use crate::Buf;
+use crate::io::{DEFAULT_BUF_SIZE, DecodeBuf, EncodeBuf};
+
+/// An async writer that hex-encodes everything written to it into an inner
+/// writer.
+///
+/// This is the async counterpart of [`HexWriter`](crate::HexWriter),
+/// implementing `tokio::io::AsyncWrite` with the `tokio` feature and
+/// `futures_io::AsyncWrite` with the `futures-io` feature. Buffered hex is
+/// written out once the buffer is full and on flush or shutdown; unlike
+/// `HexWriter`, nothing is written on drop, so always flush or shut the
+/// writer down when done.
+///
+/// ```
+/// # #[cfg(feature = "tokio")]
+/// # futures::executor::block_on(async {
+/// use muhex::AsyncHexWriter;
+/// use tokio::io::AsyncWriteExt;
+///
+/// let mut writer = AsyncHexWriter::new(Vec::new());
+/// writer.write_all(b"Hello world!").await?;
+/// writer.flush().await?;
+/// assert_eq!(writer.into_inner(), b"48656c6c6f20776f726c6421");
+/// # Ok::<(), std::io::Error>(())
+/// # }).unwrap();
+/// ```
+pub struct AsyncHexWriter<W> {
+    inner: W,
+    buf: EncodeBuf,
+}
+
+impl<W> AsyncHexWriter<W> {
+    /// Creates an `AsyncHexWriter` that writes lowercase hex to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates an `AsyncHexWriter` that writes uppercase hex to `inner`.
+    pub fn new_upper(inner: W) -> Self {
+        Self::with_capacity_upper(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates an `AsyncHexWriter` whose internal buffer holds `capacity`
+    /// hex characters, rounded up to at least 2.
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: EncodeBuf::new(capacity, false),
+        }
+    }
+
+    /// Like [`with_capacity`](Self::with_capacity), but writes uppercase
+    /// hex.
+    pub fn with_capacity_upper(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: EncodeBuf::new(capacity, true),
+        }
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    ///
+    /// Writing to it directly may interleave with hex that is still
+    /// buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns a pinned mutable reference to the inner writer.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut W> {
+        self.project().0
+    }
+
+    /// Returns the inner writer, discarding any hex that was not flushed.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn project(self: Pin<&mut Self>) -> (Pin<&mut W>, &mut EncodeBuf) {
+        // SAFETY: `inner` is structurally pinned: it is never moved out of
+        // a pinned `self`, and there is no `Drop` impl that could do so
+        let this = unsafe { self.get_unchecked_mut() };
+        (
+            unsafe { Pin::new_unchecked(&mut this.inner) },
+            &mut this.buf,
+        )
+    }
+
+    /// Writes the buffered hex to the inner writer with `write`.
+    fn poll_flush_buf(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        write: impl Fn(
+            Pin<&mut W>,
+            &mut Context<'_>,
+            &[u8],
+        ) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<()>> {
+        let (mut inner, buf) = self.project();
+
+        while !buf.pending().is_empty() {
+            match ready!(write(inner.as_mut(), cx, buf.pending()))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                n => buf.consume(n),
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_write_with(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+        write: impl Fn(
+            Pin<&mut W>,
+            &mut Context<'_>,
+            &[u8],
+        ) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<usize>> {
+        if self.buf.should_flush(data.len()) {
+            ready!(self.as_mut().poll_flush_buf(cx, write))?;
+        }
+
+        Poll::Ready(Ok(self.project().1.encode(data)))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W: tokio::io::AsyncWrite> tokio::io::AsyncWrite for AsyncHexWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_with(cx, data, W::poll_write)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush_buf(cx, W::poll_write))?;
+        self.project().0.poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush_buf(cx, W::poll_write))?;
+        self.project().0.poll_shutdown(cx)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<W: futures_io::AsyncWrite> futures_io::AsyncWrite for AsyncHexWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_with(cx, data, W::poll_write)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush_buf(cx, W::poll_write))?;
+        self.project().0.poll_flush(cx)
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush_buf(cx, W::poll_write))?;
+        self.project().0.poll_close(cx)
+    }
+}
+
+/// An async reader that decodes the hex read from an inner reader.
+///
+/// This is the async counterpart of [`HexReader`](crate::HexReader),
+/// implementing `tokio::io::AsyncRead` with the `tokio` feature and
+/// `futures_io::AsyncRead` with the `futures-io` feature, and reporting
+/// errors the same way.
+///
+/// ```
+/// # #[cfg(feature = "tokio")]
+/// # futures::executor::block_on(async {
+/// use muhex::AsyncHexReader;
+/// use tokio::io::AsyncReadExt;
+///
+/// let mut reader = AsyncHexReader::new(&b"48656c6c6f20776f726c6421"[..]);
+/// let mut decoded = String::new();
+/// reader.read_to_string(&mut decoded).await?;
+/// assert_eq!(decoded, "Hello world!");
+/// # Ok::<(), std::io::Error>(())
+/// # }).unwrap();
+/// ```
+pub struct AsyncHexReader<R> {
+    inner: R,
+    buf: DecodeBuf,
+}
+
+impl<R> AsyncHexReader<R> {
+    /// Creates an `AsyncHexReader` that decodes the hex read from `inner`.
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates an `AsyncHexReader` whose internal buffer holds `capacity`
+    /// hex characters, rounded up to at least 2.
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buf: DecodeBuf::new(capacity),
+        }
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner reader.
+    ///
+    /// Reading from it directly skips over hex that is still buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns a pinned mutable reference to the inner reader.
+    pub fn get_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+        self.project().0
+    }
+
+    /// Returns the inner reader, discarding any buffered hex.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn project(self: Pin<&mut Self>) -> (Pin<&mut R>, &mut DecodeBuf) {
+        // SAFETY: `inner` is structurally pinned: it is never moved out of
+        // a pinned `self`, and there is no `Drop` impl that could do so
+        let this = unsafe { self.get_unchecked_mut() };
+        (
+            unsafe { Pin::new_unchecked(&mut this.inner) },
+            &mut this.buf,
+        )
+    }
+
+    /// Decodes into `out`, reading more hex with `read` when less than a
+    /// byte's worth is buffered.
+    fn poll_read_with(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        out: &mut [MaybeUninit<u8>],
+        read: impl Fn(
+            Pin<&mut R>,
+            &mut Context<'_>,
+            &mut [u8],
+        ) -> Poll<io::Result<usize>>,
+    ) -> Poll<io::Result<usize>> {
+        if out.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let (mut inner, buf) = self.project();
+        while !buf.has_pair() {
+            match ready!(read(inner.as_mut(), cx, buf.spare()))? {
+                0 => return Poll::Ready(Ok(buf.finish().map(|()| 0)?)),
+                n => buf.fill(n),
+            }
+        }
+
+        Poll::Ready(Ok(buf.decode(out)?))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead> tokio::io::AsyncRead for AsyncHexReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        out: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        fn read<R: tokio::io::AsyncRead>(
+            inner: Pin<&mut R>,
+            cx: &mut Context<'_>,
+            spare: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let mut spare = tokio::io::ReadBuf::new(spare);
+            ready!(inner.poll_read(cx, &mut spare))?;
+            Poll::Ready(Ok(spare.filled().len()))
+        }
+
+        // SAFETY: only initialized bytes are written to the unfilled part
+        let unfilled = unsafe { out.unfilled_mut() };
+        let n = ready!(self.poll_read_with(cx, unfilled, read::<R>))?;
+        // SAFETY: the first `n` unfilled bytes were just decoded into
+        unsafe { out.assume_init(n) };
+        out.advance(n);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncRead> futures_io::AsyncRead for AsyncHexReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        out: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // SAFETY: decoding only writes initialized bytes
+        self.poll_read_with(cx, unsafe { out.dst() }, R::poll_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use futures::executor::block_on;
+    use proptest::prelude::*;
+
+    use super::{AsyncHexReader, AsyncHexWriter};
+    use crate::Error;
+    #[cfg(feature = "tokio")]
+    use crate::{encode, encode_upper};
+
+    fn muhex_error(err: &io::Error) -> Option<&Error> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test_strategy::proptest(cases = 200)]
+    fn test_tokio_writer_duplex(
+        input: Vec<u8>,
+        #[strategy(1usize..64)] capacity: usize,
+        #[strategy(1usize..32)] pipe: usize,
+        upper: bool,
+    ) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (tx, mut rx) = tokio::io::duplex(pipe);
+        let mut writer = match upper {
+            false => AsyncHexWriter::with_capacity(capacity, tx),
+            true => AsyncHexWriter::with_capacity_upper(capacity, tx),
+        };
+
+        let hex = block_on(async {
+            let write = async {
+                writer.write_all(&input).await?;
+                writer.shutdown().await
+            };
+            let mut hex = Vec::new();
+            let (written, read) =
+                futures::join!(write, rx.read_to_end(&mut hex));
+            written.and(read).map(|_| hex)
+        })?;
+        let expected = match upper {
+            false => encode(&input),
+            true => encode_upper(&input),
+        };
+
+        prop_assert_eq!(String::from_utf8(hex).unwrap(), expected);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test_strategy::proptest(cases = 200)]
+    fn test_tokio_reader_duplex(
+        input: Vec<u8>,
+        #[strategy(2usize..64)] capacity: usize,
+        #[strategy(1usize..32)] pipe: usize,
+    ) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut tx, rx) = tokio::io::duplex(pipe);
+        let mut reader = AsyncHexReader::with_capacity(capacity, rx);
+        let hex = encode(&input);
+
+        let decoded = block_on(async {
+            let write = async {
+                tx.write_all(hex.as_bytes()).await?;
+                tx.shutdown().await
+            };
+            let mut decoded = Vec::new();
+            let (written, read) =
+                futures::join!(write, reader.read_to_end(&mut decoded));
+            written.and(read).map(|_| decoded)
+        })?;
+
+        prop_assert_eq!(decoded, input);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_tokio_reader_errors() {
+        use tokio::io::AsyncReadExt;
+
+        let mut reader = AsyncHexReader::with_capacity(4, &b"0011223x"[..]);
+        let err = block_on(reader.read_to_end(&mut Vec::new())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            muhex_error(&err),
+            Some(&Error::InvalidChar {
+                byte: b'x',
+                index: 7
+            })
+        );
+
+        let mut reader = AsyncHexReader::new(&b"00112"[..]);
+        let err = block_on(reader.read_to_end(&mut Vec::new())).unwrap_err();
+        assert_eq!(muhex_error(&err), Some(&Error::OddLength));
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test_strategy::proptest(cases = 200)]
+    fn test_futures_roundtrip_duplex(
+        input: Vec<u8>,
+        #[strategy(2usize..64)] capacity: usize,
+        #[strategy(1usize..32)] pipe: usize,
+    ) {
+        use futures::{AsyncReadExt, AsyncWriteExt};
+        use tokio_util::compat::TokioAsyncReadCompatExt;
+
+        let (tx, rx) = tokio::io::duplex(pipe);
+        let mut writer = AsyncHexWriter::with_capacity(capacity, tx.compat());
+        let mut reader = AsyncHexReader::with_capacity(capacity, rx.compat());
+
+        let decoded = block_on(async {
+            let write = async {
+                writer.write_all(&input).await?;
+                writer.close().await
+            };
+            let mut decoded = Vec::new();
+            let (written, read) =
+                futures::join!(write, reader.read_to_end(&mut decoded));
+            written.and(read).map(|_| decoded)
+        })?;
+
+        prop_assert_eq!(decoded, input);
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn test_futures_reader_errors() {
+        use futures::{AsyncReadExt, io::Cursor};
+
+        let mut reader =
+            AsyncHexReader::with_capacity(4, Cursor::new(b"00112233gg"));
+        let err = block_on(reader.read_to_end(&mut Vec::new())).unwrap_err();
+        assert_eq!(
+            muhex_error(&err),
+            Some(&Error::InvalidChar {
+                byte: b'g',
+                index: 8
+            })
+        );
+    }
+}
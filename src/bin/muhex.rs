@@ -0,0 +1,669 @@
+//! `muhex`, a command-line hex encoder and decoder that can stand in for
+//! `xxd -p` and `xxd -r -p` in shell pipelines.
+
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use muhex::{
+    Case,
+    DecodeOptions,
+    EncodeOptions,
+    Error,
+    IgnoreSet,
+    dump::{self, DumpOptions, DumpWriter, ParseOptions},
+};
+
+const USAGE: &str = "\
+Usage: muhex <COMMAND> [OPTIONS] [FILE]
+
+Commands:
+  encode  Encode bytes as hex, like `xxd -p`
+  decode  Decode hex into bytes, like `xxd -r -p`
+  dump    Write a `hexdump -C` style dump
+  undump  Read the bytes back from an xxd, hexdump, od or Wireshark dump
+
+Reads FILE, or stdin if it is missing or `-`, and writes to stdout.
+
+Options:
+  -o, --output <FILE>        Write to FILE instead of stdout
+  -u, --upper                encode, dump: write uppercase digits
+                             decode: accept only uppercase digits
+  -p, --prefix               encode: start the output with `0x`
+                             decode: require and skip a leading `0x`
+  -c, --cols <N>             encode: break lines after N bytes
+                             dump: write N bytes per line
+  -w, --ignore-whitespace    decode: skip whitespace anywhere in the input
+      --xxd                  dump: write in the style of `xxd`
+  -h, --help                 Print this help
+";
+
+/// Bytes read from the input at a time by `encode` and `decode`.
+const BLOCK_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Encode,
+    Decode,
+    Dump,
+    Undump,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Command::Encode => "encode",
+            Command::Decode => "decode",
+            Command::Dump => "dump",
+            Command::Undump => "undump",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Args {
+    command: Command,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    upper: bool,
+    prefix: bool,
+    cols: Option<usize>,
+    ignore_whitespace: bool,
+    xxd: bool,
+}
+
+impl Args {
+    /// Parses the arguments after the program name, returning `None` if
+    /// they ask for help.
+    fn parse(
+        mut args: impl Iterator<Item = OsString>,
+    ) -> Result<Option<Self>, String> {
+        use Command::*;
+
+        let command = match args.next() {
+            None => return Err("missing command".into()),
+            Some(command) => match command.to_str() {
+                Some("-h" | "--help") => return Ok(None),
+                Some("encode") => Encode,
+                Some("decode") => Decode,
+                Some("dump") => Dump,
+                Some("undump") => Undump,
+                _ => {
+                    let command = command.to_string_lossy();
+                    return Err(format!("unknown command '{command}'"));
+                }
+            },
+        };
+        let mut parsed = Args {
+            command,
+            input: None,
+            output: None,
+            upper: false,
+            prefix: false,
+            cols: None,
+            ignore_whitespace: false,
+            xxd: false,
+        };
+
+        while let Some(arg) = args.next() {
+            let flag = match arg.to_str() {
+                Some(flag) if flag.starts_with('-') && flag != "-" => flag,
+                _ if parsed.input.is_none() => {
+                    parsed.input = Some(arg.into());
+                    continue;
+                }
+                _ => {
+                    let arg = arg.to_string_lossy();
+                    return Err(format!("unexpected argument '{arg}'"));
+                }
+            };
+
+            let commands: &[Command] = match flag {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => {
+                    let Some(output) = args.next() else {
+                        return Err(format!("'{flag}' needs a file"));
+                    };
+                    parsed.output = Some(output.into());
+                    &[Encode, Decode, Dump, Undump]
+                }
+                "-u" | "--upper" => {
+                    parsed.upper = true;
+                    &[Encode, Decode, Dump]
+                }
+                "-p" | "--prefix" => {
+                    parsed.prefix = true;
+                    &[Encode, Decode]
+                }
+                "-c" | "--cols" => {
+                    let cols = args.next().and_then(|cols| {
+                        cols.to_str()?.parse().ok().filter(|&cols| cols > 0)
+                    });
+                    let Some(cols) = cols else {
+                        return Err(format!(
+                            "'{flag}' needs a positive number"
+                        ));
+                    };
+                    parsed.cols = Some(cols);
+                    &[Encode, Dump]
+                }
+                "-w" | "--ignore-whitespace" => {
+                    parsed.ignore_whitespace = true;
+                    &[Decode]
+                }
+                "--xxd" => {
+                    parsed.xxd = true;
+                    &[Dump]
+                }
+                _ => return Err(format!("unknown option '{flag}'")),
+            };
+            if !commands.contains(&command) {
+                return Err(format!("'{flag}' does not apply to {command}"));
+            }
+        }
+
+        if command == Dump
+            && parsed
+                .cols
+                .is_some_and(|cols| cols > dump::MAX_BYTES_PER_LINE)
+        {
+            let max = dump::MAX_BYTES_PER_LINE;
+            return Err(format!("dump takes at most {max} bytes per line"));
+        }
+
+        Ok(Some(parsed))
+    }
+
+    /// Opens the input and output and runs the command.
+    fn run(&self) -> io::Result<()> {
+        let mut input: Box<dyn Read> = match &self.input {
+            Some(path) if path.as_os_str() != "-" => {
+                Box::new(File::open(path).map_err(|err| with_path(err, path))?)
+            }
+            _ => Box::new(io::stdin().lock()),
+        };
+        let output: Box<dyn Write> = match &self.output {
+            Some(path) if path.as_os_str() != "-" => Box::new(
+                File::create(path).map_err(|err| with_path(err, path))?,
+            ),
+            _ => Box::new(io::stdout().lock()),
+        };
+        let mut output = BufWriter::new(output);
+
+        match self.command {
+            Command::Encode => encode(&mut input, &mut output, self, BLOCK_LEN),
+            Command::Decode => decode(&mut input, &mut output, self, BLOCK_LEN),
+            Command::Dump => dump(&mut input, &mut output, self),
+            Command::Undump => undump(&mut input, &mut output),
+        }?;
+        output.flush()
+    }
+}
+
+/// Adds `path` to the message of `err`.
+fn with_path(err: io::Error, path: &Path) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {err}", path.display()))
+}
+
+/// Reads into `buf` until it is full or the input ends, returning the
+/// number of bytes read.
+fn read_full(input: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match input.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+/// Encodes `input` in blocks of about `block_len` bytes, ending the output
+/// with a newline like `xxd -p`.
+fn encode(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    args: &Args,
+    block_len: usize,
+) -> io::Result<()> {
+    let mut options = EncodeOptions::new().upper(args.upper);
+    let mut block_len = block_len;
+    if let Some(cols) = args.cols {
+        // Blocks hold whole lines, so they join with a newline
+        options = options.line_width(cols);
+        block_len = (block_len / cols).max(1) * cols;
+    }
+
+    let mut data = vec![0; block_len];
+    let mut hex = vec![0; options.encoded_len(block_len)];
+    let mut written = 0;
+    if args.prefix {
+        output.write_all(b"0x")?;
+    }
+
+    loop {
+        let len = read_full(input, &mut data)?;
+        if len == 0 {
+            break;
+        }
+        if args.cols.is_some() && written > 0 {
+            output.write_all(b"\n")?;
+        }
+
+        let hex = &mut hex[..options.encoded_len(len)];
+        muhex::encode_with_to_buf(&data[..len], hex, options)?;
+        output.write_all(hex)?;
+        written += len;
+
+        if len < block_len {
+            break;
+        }
+    }
+
+    match written > 0 || args.prefix {
+        true => output.write_all(b"\n"),
+        false => Ok(()),
+    }
+}
+
+/// Decodes `input` in blocks of `block_len` bytes, carrying a digit that
+/// has no pair yet over to the next block.
+///
+/// Without `--ignore-whitespace`, only a single trailing line ending is
+/// allowed, as `encode` and `echo` write. Invalid characters are reported
+/// at their offset in the whole input.
+fn decode(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    args: &Args,
+    block_len: usize,
+) -> io::Result<()> {
+    let options = DecodeOptions {
+        case: if args.upper { Case::Upper } else { Case::Any },
+        ..DecodeOptions::default()
+    };
+    let ignore = IgnoreSet::WHITESPACE;
+
+    // Room for a carried digit and line ending before each block
+    let mut buf = vec![0; 3 + block_len];
+    let mut bytes = vec![0; buf.len() / 2];
+    let mut carry = 0;
+    // Offsets in the input of the carried bytes and of the next block
+    let mut carry_from = 0;
+    let mut read_from = 0;
+    let mut first = true;
+
+    loop {
+        let read = read_full(input, &mut buf[carry..carry + block_len])?;
+        let end = carry + read;
+        let eof = read < block_len;
+        let block_from = read_from;
+        read_from += read;
+        let offset = |index: usize| match index.checked_sub(carry) {
+            Some(index) => block_from + index,
+            None => carry_from + index,
+        };
+
+        let start = match &buf[..end] {
+            [b'0', b'x' | b'X', ..] if first && args.prefix => 2,
+            _ if first && args.prefix => {
+                return Err(Error::MissingPrefix { index: 0 }.into());
+            }
+            _ => 0,
+        };
+        first = false;
+        let at = |err| match err {
+            Error::InvalidChar { byte, index } => Error::InvalidChar {
+                byte,
+                index: offset(start + index),
+            },
+            err => err,
+        };
+
+        let hex = &buf[start..end];
+        let (split, carry_end, len) = if args.ignore_whitespace {
+            let digits = hex.iter().filter(|&&byte| !ignore.contains(byte));
+            let (split, carry_end) = match digits.count() % 2 {
+                1 if !eof => {
+                    let last = hex
+                        .iter()
+                        .rposition(|&byte| !ignore.contains(byte))
+                        .expect("An odd number of digits is never zero");
+                    (start + last, start + last + 1)
+                }
+                _ => (end, end),
+            };
+            let hex = &buf[start..split];
+            let len = muhex::decode_lenient_with(
+                hex,
+                &mut bytes[..],
+                ignore,
+                options.case,
+            )
+            .map_err(at)?;
+            (split, carry_end, len)
+        } else {
+            let hex = hex.strip_suffix(b"\n").unwrap_or(hex);
+            let hex = hex.strip_suffix(b"\r").unwrap_or(hex);
+            let split = match hex.len() % 2 {
+                1 if !eof => start + hex.len() - 1,
+                _ => start + hex.len(),
+            };
+            let len = (split - start).div_ceil(2);
+            let hex = &buf[start..split];
+            muhex::decode_to_buf_with(hex, &mut bytes[..len], options)
+                .map_err(at)?;
+            (split, end, len)
+        };
+        output.write_all(&bytes[..len])?;
+
+        if eof {
+            return Ok(());
+        }
+        carry_from = offset(split);
+        buf.copy_within(split..carry_end, 0);
+        carry = carry_end - split;
+    }
+}
+
+/// Dumps `input` a line at a time.
+fn dump(
+    input: &mut dyn Read,
+    output: &mut dyn Write,
+    args: &Args,
+) -> io::Result<()> {
+    let mut options = match args.xxd {
+        true => DumpOptions::xxd(),
+        false => DumpOptions::hexdump(),
+    };
+    options = options.upper(args.upper);
+    if let Some(cols) = args.cols {
+        options = options.bytes_per_line(cols);
+    }
+
+    let mut writer = DumpWriter::new(output, options);
+    io::copy(input, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Reads a dump from `input` a line at a time and writes the bytes it
+/// shows.
+///
+/// Nothing is held in memory beyond a line, so the dump may be as long as
+/// its offsets allow.
+fn undump(input: &mut dyn Read, output: &mut dyn Write) -> io::Result<()> {
+    let options = ParseOptions::new().max_len(usize::MAX);
+    dump::parse_io(BufReader::new(input), output, options)
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(env::args_os().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprint!("muhex: {err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match args.run() {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader went away, as `head` does once it has enough
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("muhex: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use muhex::Error;
+    use proptest::{collection::vec, prelude::*};
+
+    use super::{Args, Command, decode, dump, encode, undump};
+
+    fn args(args: &str) -> Result<Option<Args>, String> {
+        Args::parse(args.split_whitespace().map(Into::into))
+    }
+
+    fn run(
+        command: fn(
+            &mut dyn std::io::Read,
+            &mut dyn std::io::Write,
+            &Args,
+            usize,
+        ) -> std::io::Result<()>,
+        flags: &str,
+        input: &[u8],
+        block_len: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let args = args(flags).unwrap().unwrap();
+        let mut output = Vec::new();
+        command(&mut &input[..], &mut output, &args, block_len).map_err(
+            |err| *err.into_inner().unwrap().downcast::<Error>().unwrap(),
+        )?;
+        Ok(output)
+    }
+
+    #[test_strategy::proptest(cases = 500)]
+    fn test_cli_roundtrip(
+        #[strategy(vec(any::<u8>(), 0..300))] data: Vec<u8>,
+        #[strategy(2usize..70)] block_len: usize,
+        #[strategy(prop::option::of(1usize..40))] cols: Option<usize>,
+        upper: bool,
+        prefix: bool,
+    ) {
+        let mut flags = String::from("encode");
+        if let Some(cols) = cols {
+            flags += &format!(" --cols {cols}");
+        }
+        if upper {
+            flags += " --upper";
+        }
+        if prefix {
+            flags += " --prefix";
+        }
+        let hex = run(encode, &flags, &data, block_len)?;
+
+        let mut expected = String::from(if prefix { "0x" } else { "" });
+        let mut options = muhex::EncodeOptions::new().upper(upper);
+        if let Some(cols) = cols {
+            options = options.line_width(cols);
+        }
+        expected += &muhex::encode_with(&data, options);
+        if !expected.is_empty() {
+            expected.push('\n');
+        }
+        prop_assert_eq!(&hex, expected.as_bytes());
+
+        let flags = match (cols.is_some(), prefix) {
+            (true, true) => "decode -w -p",
+            (true, false) => "decode -w",
+            (false, true) => "decode -p",
+            (false, false) => "decode",
+        };
+        prop_assert_eq!(&run(decode, flags, &hex, block_len)?, &data);
+    }
+
+    #[test]
+    fn test_cli_decode() {
+        let decoded = |flags, input: &str, block_len| {
+            run(decode, flags, input.as_bytes(), block_len)
+        };
+
+        assert_eq!(
+            decoded("decode", "c0ffee\r\n", 3),
+            Ok(vec![0xc0, 0xff, 0xee])
+        );
+        assert_eq!(
+            decoded("decode", "c0ffee\n", 6),
+            Ok(vec![0xc0, 0xff, 0xee])
+        );
+        assert_eq!(
+            decoded("decode", "c0ff\neeee", 4),
+            Err(Error::InvalidChar {
+                byte: b'\n',
+                index: 4
+            })
+        );
+        assert_eq!(decoded("decode", "c0ffe\n", 4), Err(Error::OddLength));
+        assert_eq!(
+            decoded("decode -u", "C0FFeE", 4),
+            Err(Error::InvalidChar {
+                byte: b'e',
+                index: 4
+            })
+        );
+        assert_eq!(
+            decoded("decode -w", " c 0\n\tf f e  e ", 3),
+            Ok(vec![0xc0, 0xff, 0xee])
+        );
+        assert_eq!(
+            decoded("decode -w", "c0 f      x", 4),
+            Err(Error::InvalidChar {
+                byte: b'x',
+                index: 10
+            })
+        );
+        assert_eq!(
+            decoded("decode -u -w", "C0 F\nF E\ne", 3),
+            Err(Error::InvalidChar {
+                byte: b'e',
+                index: 9
+            })
+        );
+        assert_eq!(
+            decoded("decode -u -w", "C0 F\nF E\nE", 3),
+            Ok(vec![0xc0, 0xff, 0xee])
+        );
+        assert_eq!(
+            decoded("decode -w", "c0 f      ", 4),
+            Err(Error::OddLength)
+        );
+        assert_eq!(
+            decoded("decode -p", "c0ffee", 4),
+            Err(Error::MissingPrefix { index: 0 })
+        );
+    }
+
+    #[test_strategy::proptest(cases = 200)]
+    fn test_cli_dump(
+        #[strategy(vec(0u8..2, 0..3000))] data: Vec<u8>,
+        #[strategy(1usize..8)] cols: usize,
+        xxd: bool,
+    ) {
+        let flags = match xxd {
+            true => format!("dump --xxd -c {cols}"),
+            false => format!("dump -c {cols}"),
+        };
+        let args = args(&flags).unwrap().unwrap();
+        let mut text = Vec::new();
+        dump(&mut &data[..], &mut text, &args)?;
+
+        let options = match xxd {
+            true => muhex::dump::DumpOptions::xxd(),
+            false => muhex::dump::DumpOptions::hexdump(),
+        };
+        let expected =
+            muhex::dump::to_string(&data, options.bytes_per_line(cols));
+        prop_assert_eq!(&text, expected.as_bytes());
+
+        let mut bytes = Vec::new();
+        undump(&mut &text[..], &mut bytes)?;
+        prop_assert_eq!(bytes, data);
+    }
+
+    /// Stdout that takes at most `max` bytes a write, is interrupted every
+    /// third write and closes once `limit` bytes went through.
+    struct Pipe {
+        out: Vec<u8>,
+        max: usize,
+        limit: usize,
+        writes: usize,
+    }
+
+    impl std::io::Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            use std::io::ErrorKind;
+
+            self.writes += 1;
+            if self.writes % 3 == 0 {
+                return Err(ErrorKind::Interrupted.into());
+            }
+            let len = buf.len().min(self.max).min(self.limit - self.out.len());
+            if len == 0 && !buf.is_empty() {
+                return Err(ErrorKind::BrokenPipe.into());
+            }
+            self.out.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test_strategy::proptest(cases = 200)]
+    fn test_cli_dump_short_writes(
+        #[strategy(vec(0u8..2, 0..2000))] data: Vec<u8>,
+        #[strategy(1usize..50)] max: usize,
+        #[strategy(0usize..20000)] limit: usize,
+    ) {
+        let args = args("dump -c 4").unwrap().unwrap();
+        let options = muhex::dump::DumpOptions::hexdump().bytes_per_line(4);
+        let expected = muhex::dump::to_string(&data, options);
+
+        // The output stops where the pipe closed, with no line repeated
+        // or lost before it
+        let mut pipe = Pipe {
+            out: Vec::new(),
+            max,
+            limit,
+            writes: 0,
+        };
+        let result = dump(&mut &data[..], &mut pipe, &args);
+        let end = expected.len().min(limit);
+        prop_assert_eq!(result.is_ok(), expected.len() <= limit);
+        prop_assert_eq!(&pipe.out, &expected.as_bytes()[..end]);
+    }
+
+    #[test]
+    fn test_cli_args() {
+        assert_eq!(
+            args("dump --xxd -c 8 -u file -o out"),
+            Ok(Some(Args {
+                command: Command::Dump,
+                input: Some("file".into()),
+                output: Some("out".into()),
+                upper: true,
+                prefix: false,
+                cols: Some(8),
+                ignore_whitespace: false,
+                xxd: true,
+            }))
+        );
+        assert_eq!(args("encode --help"), Ok(None));
+        assert!(args("").is_err());
+        assert!(args("hexify").is_err());
+        assert!(args("encode --xxd").is_err());
+        assert!(args("encode -c 0").is_err());
+        assert!(args("dump -c 257").is_err());
+        assert!(args("undump a b").is_err());
+    }
+}
@@ -1,10 +1,10 @@
-use std::mem::MaybeUninit;
+use core::mem::MaybeUninit;
 
 /// Private sealing trait - prevents external implementations
 mod private {
     pub trait Sealed {}
 
-    impl Sealed for [std::mem::MaybeUninit<u8>] {}
+    impl Sealed for [core::mem::MaybeUninit<u8>] {}
     impl Sealed for [u8] {}
 }
 
@@ -39,7 +39,10 @@ impl Buf for [u8] {
         // 2. The lifetime of the reference is tied to &mut self, preventing aliasing
         // 3. The caller is responsible for only writing initialized bytes (per trait contract)
         unsafe {
-            std::slice::from_raw_parts_mut(self.as_mut_ptr().cast(), self.len())
+            core::slice::from_raw_parts_mut(
+                self.as_mut_ptr().cast(),
+                self.len(),
+            )
         }
     }
 }
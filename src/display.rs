@@ -0,0 +1,171 @@
+// Lazy formatting of byte slices as hex, without allocating.
+
+use core::{
+    fmt::{self, Alignment, Write},
+    str,
+};
+
+use crate::{HEX_ENCODE_LUT, HEX_ENCODE_LUT_UPPER, encode_to_buf_with_lut};
+
+/// Number of bytes encoded per write to the formatter.
+const CHUNK_LEN: usize = 256;
+
+/// Wraps `bytes` so they format as hex, see [`HexDisplay`].
+///
+/// ```
+/// let digest = [0xde, 0xad, 0xbe, 0xef];
+/// assert_eq!(format!("{}", muhex::display(&digest)), "deadbeef");
+/// assert_eq!(format!("{:#X}", muhex::display(&digest)), "0xDEADBEEF");
+/// ```
+#[inline]
+pub fn display<T: AsRef<[u8]> + ?Sized>(bytes: &T) -> HexDisplay<'_> {
+    HexDisplay::new(bytes.as_ref())
+}
+
+/// Formats a byte slice as hex when displayed, encoding it in small chunks
+/// on the stack instead of allocating a `String`.
+///
+/// `Display` and `LowerHex` emit lowercase digits and `UpperHex` uppercase
+/// ones. Width, fill and alignment are honoured, with `Display` aligning
+/// left by default like strings and the hex traits aligning right like
+/// integers. The `#` flag adds a `0x` prefix and the `0` flag pads with
+/// zeros after it, so `{:#010x}` formats four bytes like the equivalent
+/// big-endian `u32`.
+#[derive(Debug, Clone, Copy)]
+pub struct HexDisplay<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> HexDisplay<'a> {
+    /// Wraps `bytes` so they format as hex.
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn fmt_with(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        lut: &[u8; 16],
+        default_align: Alignment,
+    ) -> fmt::Result {
+        let prefix = if f.alternate() { "0x" } else { "" };
+        let len = prefix.len() + self.bytes.len() * 2;
+        let padding = f.width().map_or(0, |width| width.saturating_sub(len));
+
+        if f.sign_aware_zero_pad() {
+            f.write_str(prefix)?;
+            write_fill(f, '0', padding)?;
+            return self.write_hex(f, lut);
+        }
+
+        let (before, after) = match f.align().unwrap_or(default_align) {
+            Alignment::Left => (0, padding),
+            Alignment::Right => (padding, 0),
+            Alignment::Center => (padding / 2, padding - padding / 2),
+        };
+        let fill = f.fill();
+
+        write_fill(f, fill, before)?;
+        f.write_str(prefix)?;
+        self.write_hex(f, lut)?;
+        write_fill(f, fill, after)
+    }
+
+    fn write_hex(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        lut: &[u8; 16],
+    ) -> fmt::Result {
+        let mut buf = [0; CHUNK_LEN * 2];
+
+        for chunk in self.bytes.chunks(CHUNK_LEN) {
+            let hex = &mut buf[..chunk.len() * 2];
+            encode_to_buf_with_lut(chunk, hex, lut)
+                .expect("Len of the chunk buffer is always correct");
+            // SAFETY: hex digits are ASCII
+            f.write_str(unsafe { str::from_utf8_unchecked(hex) })?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_fill(f: &mut fmt::Formatter<'_>, fill: char, n: usize) -> fmt::Result {
+    (0..n).try_for_each(|_| f.write_char(fill))
+}
+
+impl fmt::Display for HexDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &HEX_ENCODE_LUT, Alignment::Left)
+    }
+}
+
+impl fmt::LowerHex for HexDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &HEX_ENCODE_LUT, Alignment::Right)
+    }
+}
+
+impl fmt::UpperHex for HexDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, &HEX_ENCODE_LUT_UPPER, Alignment::Right)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::display;
+    use crate::{encode, encode_upper};
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_display_parity(
+        #[strategy(vec(any::<u8>(), 0..1024))] input: Vec<u8>,
+        #[strategy(0usize..2100)] width: usize,
+    ) {
+        let hex = encode(&input);
+        let upper = encode_upper(&input);
+        let shown = display(&input);
+
+        prop_assert_eq!(&format!("{shown}"), &hex);
+        prop_assert_eq!(&format!("{shown:x}"), &hex);
+        prop_assert_eq!(&format!("{shown:X}"), &upper);
+        prop_assert_eq!(format!("{shown:#x}"), format!("0x{hex}"));
+        prop_assert_eq!(format!("{shown:width$}"), format!("{hex:width$}"));
+        prop_assert_eq!(
+            format!("{shown:*^width$x}"),
+            format!("{hex:*^width$}")
+        );
+        prop_assert_eq!(format!("{shown:width$X}"), format!("{upper:>width$}"));
+        prop_assert_eq!(
+            format!("{shown:<#width$x}"),
+            format!("{:<width$}", format!("0x{hex}"))
+        );
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_display_matches_integers(
+        input: u32,
+        #[strategy(0usize..16)] width: usize,
+    ) {
+        // Integers drop leading zeros, so pad them to the full byte width
+        let bytes = input.to_be_bytes();
+        let shown = display(&bytes);
+        let (prefixed, plain) = (width.max(10), width.max(8));
+
+        prop_assert_eq!(
+            format!("{shown:#0width$x}"),
+            format!("{input:#0prefixed$x}")
+        );
+        prop_assert_eq!(
+            format!("{shown:#0width$X}"),
+            format!("{input:#0prefixed$X}")
+        );
+        prop_assert_eq!(
+            format!("{shown:0width$x}"),
+            format!("{input:0plain$x}")
+        );
+    }
+}
@@ -0,0 +1,872 @@
+//! Hex dumps in the style of `hexdump -C` and `xxd`, with an offset column,
+//! the bytes in hex and an ASCII gutter.
+//!
+//! ```
+//! use muhex::dump::{self, DumpOptions};
+//!
+//! let mut out = String::new();
+//! dump::write(&mut out, b"Hello, world.\n", DumpOptions::xxd())?;
+//! assert_eq!(
+//!     out,
+//!     "00000000: 4865 6c6c 6f2c 2077 6f72 6c64 2e0a       Hello, world..\n"
+//! );
+//! # Ok::<(), core::fmt::Error>(())
+//! ```
+
+#[cfg(feature = "alloc")]
+mod parse;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::{fmt, str};
+
+#[cfg(feature = "std")]
+pub use parse::parse_io;
+#[cfg(feature = "alloc")]
+pub use parse::{
+    DEFAULT_MAX_LEN,
+    Format,
+    ParseError,
+    ParseErrorKind,
+    ParseOptions,
+    parse,
+    parse_with,
+};
+
+use crate::{
+    Buf,
+    EncodeOptions,
+    HEX_ENCODE_LUT,
+    HEX_ENCODE_LUT_UPPER,
+    encode_options_into,
+};
+
+/// Most bytes [`DumpOptions::bytes_per_line`] accepts, as with `xxd -c`.
+pub const MAX_BYTES_PER_LINE: usize = 256;
+
+/// Widest offset column [`DumpOptions::offset_width`] accepts.
+pub const MAX_OFFSET_WIDTH: usize = 32;
+
+/// Longest line: the offset and its separator, the hex columns with the gap
+/// halfway through, two spaces, the gutter between bars and a newline.
+const MAX_LINE_LEN: usize =
+    MAX_OFFSET_WIDTH + 2 + MAX_BYTES_PER_LINE * 3 + 2 + MAX_BYTES_PER_LINE + 3;
+
+/// The punctuation of the tool a dump imitates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Style {
+    /// `00000000  68 69 ... |hi|`, with an extra space halfway through the
+    /// hex columns and a last line holding the total length.
+    Hexdump,
+    /// `00000000: 6869 ...  hi`.
+    Xxd,
+}
+
+/// The base of the offset column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OffsetBase {
+    /// Hexadecimal, as `hexdump` and `xxd` print it.
+    #[default]
+    Hex,
+    /// Decimal, as with `xxd -d`.
+    Decimal,
+    /// Octal, as `od` prints it by default.
+    Octal,
+}
+
+impl OffsetBase {
+    const fn radix(self) -> usize {
+        match self {
+            OffsetBase::Hex => 16,
+            OffsetBase::Decimal => 10,
+            OffsetBase::Octal => 8,
+        }
+    }
+}
+
+/// Options for [`write()`], [`write_io`], [`to_string`] and [`DumpWriter`].
+///
+/// Start from [`hexdump`](Self::hexdump) or [`xxd`](Self::xxd) for the
+/// layout of either tool, then adjust the columns. The default is
+/// [`hexdump`](Self::hexdump).
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use muhex::dump::{self, DumpOptions, OffsetBase};
+///
+/// let options = DumpOptions::hexdump()
+///     .bytes_per_line(8)
+///     .offset_width(4)
+///     .offset_base(OffsetBase::Decimal);
+/// assert_eq!(
+///     dump::to_string(b"Hello, world.\n", options),
+///     "0000  48 65 6c 6c  6f 2c 20 77  |Hello, w|\n\
+///      0008  6f 72 6c 64  2e 0a        |orld..|\n\
+///      0014\n"
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DumpOptions {
+    style: Style,
+    bytes_per_line: usize,
+    group_size: usize,
+    offset_width: usize,
+    offset_base: OffsetBase,
+    ascii: bool,
+    squeeze: bool,
+    upper: bool,
+}
+
+impl DumpOptions {
+    /// Creates options for the canonical `hexdump -C` layout: 16 bytes per
+    /// line, an extra space after the eighth, the gutter between bars,
+    /// repeated lines squeezed into a `*` and the total length on a last
+    /// line of its own.
+    pub const fn hexdump() -> Self {
+        Self {
+            style: Style::Hexdump,
+            bytes_per_line: 16,
+            group_size: 1,
+            offset_width: 8,
+            offset_base: OffsetBase::Hex,
+            ascii: true,
+            squeeze: true,
+            upper: false,
+        }
+    }
+
+    /// Creates options for the default `xxd` layout: 16 bytes per line in
+    /// groups of 2, a `:` after the offset and no squeezing.
+    pub const fn xxd() -> Self {
+        Self {
+            style: Style::Xxd,
+            group_size: 2,
+            squeeze: false,
+            ..Self::hexdump()
+        }
+    }
+
+    /// Sets how many bytes go on each line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_line` is 0 or above [`MAX_BYTES_PER_LINE`].
+    pub const fn bytes_per_line(mut self, bytes_per_line: usize) -> Self {
+        assert!(bytes_per_line > 0, "bytes per line must not be 0");
+        assert!(
+            bytes_per_line <= MAX_BYTES_PER_LINE,
+            "bytes per line must be at most 256"
+        );
+        self.bytes_per_line = bytes_per_line;
+        self
+    }
+
+    /// Sets how many bytes go between two spaces in the hex columns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `group_size` is 0.
+    pub const fn group_size(mut self, group_size: usize) -> Self {
+        assert!(group_size > 0, "group size must not be 0");
+        self.group_size = group_size;
+        self
+    }
+
+    /// Pads offsets with zeros to at least `offset_width` digits, 8 by
+    /// default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset_width` is above [`MAX_OFFSET_WIDTH`].
+    pub const fn offset_width(mut self, offset_width: usize) -> Self {
+        assert!(
+            offset_width <= MAX_OFFSET_WIDTH,
+            "offset width must be at most 32"
+        );
+        self.offset_width = offset_width;
+        self
+    }
+
+    /// Sets the base of the offset column.
+    pub const fn offset_base(mut self, offset_base: OffsetBase) -> Self {
+        self.offset_base = offset_base;
+        self
+    }
+
+    /// Shows the bytes as ASCII after the hex columns if `ascii` is true,
+    /// with `.` for bytes that are not printable.
+    pub const fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Replaces lines that repeat the one before with a single `*` line if
+    /// `squeeze` is true. In the `xxd` layout, the last line is always
+    /// shown so the length of the data is known.
+    pub const fn squeeze(mut self, squeeze: bool) -> Self {
+        self.squeeze = squeeze;
+        self
+    }
+
+    /// Emits uppercase hex digits, in the offsets too, if `upper` is true.
+    pub const fn upper(mut self, upper: bool) -> Self {
+        self.upper = upper;
+        self
+    }
+
+    const fn lut(&self) -> &'static [u8; 16] {
+        match self.upper {
+            false => &HEX_ENCODE_LUT,
+            true => &HEX_ENCODE_LUT_UPPER,
+        }
+    }
+
+    const fn hex_options(&self) -> EncodeOptions {
+        EncodeOptions::new()
+            .separator(b' ')
+            .group_size(self.group_size)
+            .upper(self.upper)
+    }
+
+    /// Bytes before the extra space in the hex columns, or 0 for none.
+    const fn half(&self) -> usize {
+        let half = self.bytes_per_line / 2;
+        match self.style {
+            Style::Hexdump if half.is_multiple_of(self.group_size) => half,
+            _ => 0,
+        }
+    }
+
+    /// Width of the hex columns for a line holding `len` bytes.
+    const fn hex_len(&self, len: usize) -> usize {
+        let half = self.half();
+        self.hex_options().line_len(len) + (half > 0 && len > half) as usize
+    }
+
+    fn push_offset(&self, line: &mut Line, mut offset: usize) {
+        let radix = self.offset_base.radix();
+        let mut digits = [b'0'; MAX_OFFSET_WIDTH];
+        let mut start = MAX_OFFSET_WIDTH;
+
+        while offset > 0 || start > MAX_OFFSET_WIDTH - self.offset_width {
+            start -= 1;
+            digits[start] = self.lut()[offset % radix];
+            offset /= radix;
+        }
+        if start == MAX_OFFSET_WIDTH {
+            start -= 1;
+        }
+
+        line.push(&digits[start..]);
+    }
+
+    fn push_line(&self, line: &mut Line, offset: usize, bytes: &[u8]) {
+        self.push_offset(line, offset);
+        line.push(match self.style {
+            Style::Hexdump => b"  ",
+            Style::Xxd => b": ",
+        });
+
+        let (first, second) = match self.half() {
+            0 => (bytes, &[][..]),
+            half => bytes.split_at(half.min(bytes.len())),
+        };
+        line.push_hex(first, self.hex_options());
+        if !second.is_empty() {
+            line.push(b"  ");
+            line.push_hex(second, self.hex_options());
+        }
+
+        if self.ascii {
+            let padding =
+                self.hex_len(self.bytes_per_line) - self.hex_len(bytes.len());
+            line.push_repeat(b' ', padding + 2);
+
+            let bar: &[u8] = match self.style {
+                Style::Hexdump => b"|",
+                Style::Xxd => b"",
+            };
+            line.push(bar);
+            for &byte in bytes {
+                line.push(&[match byte {
+                    b' '..=b'~' => byte,
+                    _ => b'.',
+                }]);
+            }
+            line.push(bar);
+        }
+
+        line.push(b"\n");
+    }
+
+    /// Dumps `data` a line at a time through `emit`.
+    fn dump<E>(
+        &self,
+        data: &[u8],
+        mut emit: impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut dumper = Dumper::new(*self);
+        for bytes in data.chunks(self.bytes_per_line) {
+            dumper.push(bytes, &mut emit)?;
+        }
+        dumper.finish(&mut emit)
+    }
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        Self::hexdump()
+    }
+}
+
+/// A line of a dump, built up on the stack.
+struct Line {
+    buf: [u8; MAX_LINE_LEN],
+    len: usize,
+}
+
+impl Line {
+    fn new() -> Self {
+        Self {
+            buf: [0; MAX_LINE_LEN],
+            len: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn push_repeat(&mut self, byte: u8, n: usize) {
+        self.buf[self.len..self.len + n].fill(byte);
+        self.len += n;
+    }
+
+    fn push_hex(&mut self, bytes: &[u8], options: EncodeOptions) {
+        let len = options.line_len(bytes.len());
+        let dst = &mut self.buf[self.len..self.len + len];
+        // SAFETY: `encode_options_into` only writes initialized bytes
+        encode_options_into(bytes, unsafe { dst.dst() }, options);
+        self.len += len;
+    }
+
+    fn as_str(&self) -> &str {
+        // SAFETY: lines are built from ASCII only
+        unsafe { str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+}
+
+/// The state of a dump between its lines, so that it can be written a
+/// line at a time.
+struct Dumper {
+    options: DumpOptions,
+    line: Line,
+    /// The bytes of the last line written out.
+    previous: [u8; MAX_BYTES_PER_LINE],
+    previous_len: usize,
+    /// Lines since the last one written out that repeat it.
+    repeats: usize,
+    /// Offset of the next line.
+    offset: usize,
+}
+
+impl Dumper {
+    fn new(options: DumpOptions) -> Self {
+        Self {
+            options,
+            line: Line::new(),
+            previous: [0; MAX_BYTES_PER_LINE],
+            previous_len: 0,
+            repeats: 0,
+            offset: 0,
+        }
+    }
+
+    /// Dumps the next line, which holds `bytes_per_line` bytes unless it
+    /// is the last one.
+    fn push<E>(
+        &mut self,
+        bytes: &[u8],
+        emit: &mut impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let previous = &self.previous[..self.previous_len];
+        if self.options.squeeze && bytes == previous {
+            // Held back, as `xxd` shows the last line even if it repeats
+            self.repeats += 1;
+        } else {
+            if self.repeats > 0 {
+                emit("*\n")?;
+                self.repeats = 0;
+            }
+            self.line.clear();
+            self.options.push_line(&mut self.line, self.offset, bytes);
+            emit(self.line.as_str())?;
+            self.previous[..bytes.len()].copy_from_slice(bytes);
+            self.previous_len = bytes.len();
+        }
+
+        self.offset += bytes.len();
+        Ok(())
+    }
+
+    /// Writes out what is left of the dump after its last line.
+    fn finish<E>(
+        &mut self,
+        emit: &mut impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let repeats = core::mem::take(&mut self.repeats);
+        match self.options.style {
+            Style::Xxd if repeats > 0 => {
+                if repeats > 1 {
+                    emit("*\n")?;
+                }
+                let previous = &self.previous[..self.previous_len];
+                let offset = self.offset - previous.len();
+                self.line.clear();
+                self.options.push_line(&mut self.line, offset, previous);
+                emit(self.line.as_str())?;
+            }
+            _ if repeats > 0 => emit("*\n")?,
+            _ => {}
+        }
+
+        if self.options.style == Style::Hexdump && self.offset > 0 {
+            self.line.clear();
+            self.options.push_offset(&mut self.line, self.offset);
+            self.line.push(b"\n");
+            emit(self.line.as_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a dump of `data` to `out`, a line at a time.
+#[inline]
+pub fn write<T, W>(out: &mut W, data: T, options: DumpOptions) -> fmt::Result
+where
+    T: AsRef<[u8]>,
+    W: fmt::Write + ?Sized,
+{
+    options.dump(data.as_ref(), |line| out.write_str(line))
+}
+
+/// Like [`write()`], but writes to an [`io::Write`](std::io::Write).
+///
+/// ```
+/// use muhex::dump::{self, DumpOptions};
+///
+/// dump::write_io(&mut std::io::stdout(), b"\x7fELF", DumpOptions::xxd())?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+#[inline]
+pub fn write_io<T, W>(
+    out: &mut W,
+    data: T,
+    options: DumpOptions,
+) -> std::io::Result<()>
+where
+    T: AsRef<[u8]>,
+    W: std::io::Write + ?Sized,
+{
+    options.dump(data.as_ref(), |line| out.write_all(line.as_bytes()))
+}
+
+/// A writer that dumps everything written to it into an inner writer, a
+/// line at a time, for data too large to hold in memory.
+///
+/// Bytes are held back until they fill a line, and lines that repeat the
+/// one before until it is known whether they are squeezed. Call
+/// [`finish`](Self::finish) to write out the rest of the dump, which is
+/// lost if the `DumpWriter` is dropped instead. Each line is a separate
+/// write, so wrap the inner writer in a `BufWriter` if that is slow.
+///
+/// As with `BufWriter`, a line that could not be written out is kept and
+/// written before anything else, so a failed write can be retried.
+///
+/// ```
+/// use std::io::{self, Write};
+///
+/// use muhex::dump::{DumpOptions, DumpWriter};
+///
+/// let mut writer = DumpWriter::new(Vec::new(), DumpOptions::hexdump());
+/// writer.write_all(&[0; 24])?;
+/// writer.write_all(&[0; 8])?;
+/// assert_eq!(
+///     writer.finish()?,
+///     b"00000000  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|\n\
+///       *\n\
+///       00000020\n"
+/// );
+/// # Ok::<(), io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub struct DumpWriter<W: std::io::Write> {
+    inner: W,
+    dumper: Dumper,
+    /// The bytes of the line being filled.
+    pending: [u8; MAX_BYTES_PER_LINE],
+    pending_len: usize,
+    /// Dump text not written out yet, from `written` to `len`.
+    text: DumpText,
+}
+
+/// Dump text waiting to be written out: at most a `*` line, a line and
+/// the line with the total length, as the end of a dump can need.
+#[cfg(feature = "std")]
+struct DumpText {
+    buf: [u8; 2 * MAX_LINE_LEN + 2],
+    len: usize,
+    written: usize,
+}
+
+#[cfg(feature = "std")]
+impl DumpText {
+    fn push(&mut self, line: &str) -> Result<(), core::convert::Infallible> {
+        self.buf[self.len..self.len + line.len()]
+            .copy_from_slice(line.as_bytes());
+        self.len += line.len();
+        Ok(())
+    }
+
+    /// Writes out the text, keeping whatever could not be written.
+    fn write_to<W: std::io::Write>(
+        &mut self,
+        inner: &mut W,
+    ) -> std::io::Result<()> {
+        use std::io::ErrorKind;
+
+        while self.written < self.len {
+            match inner.write(&self.buf[self.written..self.len]) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => self.written += n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.len = 0;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> DumpWriter<W> {
+    /// Creates a `DumpWriter` that writes a dump laid out by `options` to
+    /// `inner`.
+    pub fn new(inner: W, options: DumpOptions) -> Self {
+        Self {
+            inner,
+            dumper: Dumper::new(options),
+            pending: [0; MAX_BYTES_PER_LINE],
+            pending_len: 0,
+            text: DumpText {
+                buf: [0; 2 * MAX_LINE_LEN + 2],
+                len: 0,
+                written: 0,
+            },
+        }
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    ///
+    /// Writing to it directly may interleave with lines that are still
+    /// held back.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes out the rest of the dump, including the last partial line,
+    /// and returns the inner writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.text.write_to(&mut self.inner)?;
+        let mut emit = |line: &str| self.text.push(line);
+        if self.pending_len > 0 {
+            let Ok(()) = self
+                .dumper
+                .push(&self.pending[..self.pending_len], &mut emit);
+        }
+        let Ok(()) = self.dumper.finish(&mut emit);
+        self.text.write_to(&mut self.inner)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for DumpWriter<W> {
+    /// Takes in at most the rest of a line, after writing out the text
+    /// of the last one.
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.text.write_to(&mut self.inner)?;
+
+        let width = self.dumper.options.bytes_per_line;
+        let len = buf.len().min(width - self.pending_len);
+        self.pending[self.pending_len..self.pending_len + len]
+            .copy_from_slice(&buf[..len]);
+        self.pending_len += len;
+
+        if self.pending_len == width {
+            let mut emit = |line: &str| self.text.push(line);
+            let Ok(()) = self.dumper.push(&self.pending[..width], &mut emit);
+            self.pending_len = 0;
+        }
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.text.write_to(&mut self.inner)?;
+        self.inner.flush()
+    }
+}
+
+/// Returns a dump of `data` as a `String`.
+///
+/// ```
+/// use muhex::dump::{self, DumpOptions};
+///
+/// assert_eq!(
+///     dump::to_string([0; 40], DumpOptions::hexdump()),
+///     "00000000  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|\n\
+///      *\n\
+///      00000020  00 00 00 00 00 00 00 00                           |........|\n\
+///      00000028\n"
+/// );
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn to_string<T: AsRef<[u8]>>(data: T, options: DumpOptions) -> String {
+    let mut out = String::new();
+    write(&mut out, data, options).expect("Writing to a String never fails");
+    out
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::{DumpOptions, OffsetBase, to_string};
+
+    /// Straightforward dump of `data` to test the line layout against.
+    fn dump_reference(data: &[u8], options: DumpOptions) -> String {
+        let DumpOptions {
+            bytes_per_line: width,
+            group_size: group,
+            ..
+        } = options;
+        let hexdump = options == DumpOptions::hexdump();
+        let mut out = String::new();
+
+        for (i, line) in data.chunks(width).enumerate() {
+            let mut hex = String::new();
+            for j in 0..width {
+                if j > 0 && j % group == 0 {
+                    hex.push(' ');
+                }
+                if hexdump && j == width / 2 {
+                    hex.push(' ');
+                }
+                match line.get(j) {
+                    Some(byte) => hex.push_str(&format!("{byte:02x}")),
+                    None => hex.push_str("  "),
+                }
+            }
+            let ascii: String = line
+                .iter()
+                .map(|&b| match b.is_ascii_graphic() || b == b' ' {
+                    true => b as char,
+                    false => '.',
+                })
+                .collect();
+
+            out.push_str(&match hexdump {
+                true => format!("{:08x}  {hex}  |{ascii}|\n", i * width),
+                false => format!("{:08x}: {hex}  {ascii}\n", i * width),
+            });
+        }
+        if hexdump && !data.is_empty() {
+            out.push_str(&format!("{:08x}\n", data.len()));
+        }
+
+        out
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_dump_parity(
+        #[strategy(vec(any::<u8>(), 0..200))] input: Vec<u8>,
+        #[strategy(1usize..40)] width: usize,
+        #[strategy(1usize..8)] group: usize,
+    ) {
+        let xxd = DumpOptions::xxd().bytes_per_line(width).group_size(group);
+        prop_assert_eq!(to_string(&input, xxd), dump_reference(&input, xxd));
+
+        // Random bytes practically never repeat a line, so nothing is
+        // squeezed
+        let hexdump = DumpOptions::hexdump();
+        prop_assert_eq!(
+            to_string(&input, hexdump),
+            dump_reference(&input, hexdump)
+        );
+    }
+
+    #[test]
+    fn test_dump_xxd() {
+        // Output of `printf 'Hello, world.\n' | xxd -g 4 -c 8 -u`
+        let options = DumpOptions::xxd()
+            .group_size(4)
+            .bytes_per_line(8)
+            .upper(true);
+        assert_eq!(
+            to_string(b"Hello, world.\n", options),
+            "00000000: 48656C6C 6F2C2077  Hello, w\n\
+             00000008: 6F726C64 2E0A      orld..\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_squeeze() {
+        let mut data = [0u8; 80];
+        data[70] = b'a';
+
+        assert_eq!(
+            to_string(data, DumpOptions::xxd().bytes_per_line(8).squeeze(true)),
+            "00000000: 0000 0000 0000 0000  ........\n\
+             *\n\
+             00000040: 0000 0000 0000 6100  ......a.\n\
+             00000048: 0000 0000 0000 0000  ........\n"
+        );
+        assert_eq!(
+            to_string(&data[..64], DumpOptions::xxd().squeeze(true)),
+            "00000000: 0000 0000 0000 0000 0000 0000 0000 0000  ................\n\
+             *\n\
+             00000030: 0000 0000 0000 0000 0000 0000 0000 0000  ................\n"
+        );
+        assert_eq!(to_string([], DumpOptions::hexdump()), "");
+    }
+
+    #[cfg(feature = "std")]
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_dump_writer(
+        #[strategy(vec(0u8..2, 0..300))] input: Vec<u8>,
+        #[strategy(1usize..6)] width: usize,
+        #[strategy(1usize..20)] chunk: usize,
+        xxd: bool,
+    ) {
+        use std::io::Write;
+
+        use super::DumpWriter;
+
+        // Bytes from two values repeat lines often, to test squeezing
+        let options = match xxd {
+            true => DumpOptions::xxd().squeeze(true),
+            false => DumpOptions::hexdump(),
+        }
+        .bytes_per_line(width);
+        let mut writer = DumpWriter::new(Vec::new(), options);
+        for bytes in input.chunks(chunk) {
+            writer.write_all(bytes)?;
+        }
+        let dump = String::from_utf8(writer.finish()?).unwrap();
+        prop_assert_eq!(dump, to_string(&input, options));
+    }
+
+    /// A writer that takes at most `max` bytes a call, and fails every
+    /// third call while `fail` is set.
+    #[cfg(feature = "std")]
+    struct Flaky {
+        out: Vec<u8>,
+        max: usize,
+        fail: bool,
+        calls: usize,
+    }
+
+    #[cfg(feature = "std")]
+    impl Flaky {
+        fn new(max: usize) -> Self {
+            Self {
+                out: Vec::new(),
+                max,
+                fail: true,
+                calls: 0,
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::io::Write for Flaky {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.calls += 1;
+            if self.fail && self.calls % 3 == 0 {
+                return Err(std::io::ErrorKind::Other.into());
+            }
+            let len = buf.len().min(self.max);
+            self.out.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test_strategy::proptest(cases = 500)]
+    fn test_dump_writer_retry(
+        #[strategy(vec(0u8..2, 0..300))] input: Vec<u8>,
+        #[strategy(1usize..6)] width: usize,
+        #[strategy(1usize..100)] max: usize,
+    ) {
+        use std::io::Write;
+
+        use super::DumpWriter;
+
+        // Retrying every failed call must neither repeat nor lose text
+        let options = DumpOptions::xxd().squeeze(true).bytes_per_line(width);
+        let mut writer = DumpWriter::new(Flaky::new(max), options);
+        let mut data = &input[..];
+        while !data.is_empty() {
+            if let Ok(len) = writer.write(data) {
+                data = &data[len..];
+            }
+        }
+        while writer.flush().is_err() {}
+        writer.get_mut().fail = false;
+        let dump = String::from_utf8(writer.finish()?.out).unwrap();
+        prop_assert_eq!(dump, to_string(&input, options));
+    }
+
+    #[test]
+    fn test_dump_offsets() {
+        let options = DumpOptions::hexdump()
+            .bytes_per_line(4)
+            .ascii(false)
+            .offset_width(3)
+            .offset_base(OffsetBase::Octal);
+        assert_eq!(
+            to_string(b"0123456789", options),
+            "000  30 31  32 33\n\
+             004  34 35  36 37\n\
+             010  38 39\n\
+             012\n"
+        );
+
+        let options = DumpOptions::xxd().offset_width(0).upper(true);
+        let dump = to_string([1; 4096], options);
+        let offsets: Vec<_> = dump.lines().map(|line| &line[..4]).collect();
+        assert_eq!(offsets[..2], ["0: 0", "10: "]);
+        assert_eq!(offsets[255], "FF0:");
+    }
+}
@@ -0,0 +1,635 @@
+// Reading dumps back into bytes, like `xxd -r`.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Case, Error, decode_into, parse::lines};
+
+/// Most bytes [`parse`] returns unless [`ParseOptions::max_len`] says
+/// otherwise, so that the `*` lines of an untrusted dump cannot ask for an
+/// arbitrarily large allocation.
+pub const DEFAULT_MAX_LEN: usize = 64 << 20;
+
+/// A dump layout [`parse_with`] can read.
+///
+/// Offsets are read as hex in every layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// `xxd`: `00000000: 4865 6c6c  He..`, with the hex columns ending at
+    /// the first two spaces in a row.
+    Xxd,
+    /// `hexdump -C`: `00000000  48 65 6c 6c  |Hell|`, with the hex columns
+    /// ending at the `|` of the gutter.
+    Hexdump,
+    /// `od -Ax -tx1`: `000000 48 65 6c 6c`, with the hex columns ending at
+    /// the first two spaces in a row, before the gutter of `-tx1z`.
+    Od,
+    /// Wireshark's "Copy as Hex Dump": `0000   48 65 6c 6c   Hell`, with
+    /// the hex columns ending at the first three spaces in a row.
+    Wireshark,
+}
+
+impl Format {
+    /// Guesses the format from the offset at the start of the line at
+    /// `index` and what follows it.
+    fn detect(index: usize, line: &[u8]) -> Result<Self, ParseError> {
+        let digits = offset_len(line);
+        let spaces = line[digits..]
+            .iter()
+            .take_while(|&&byte| byte == b' ')
+            .count();
+        match (digits, line.get(digits), spaces) {
+            (0, ..) => {
+                Err(ParseError::new(index, 0, ParseErrorKind::MissingOffset))
+            }
+            (_, Some(b':'), _) => Ok(Format::Xxd),
+            (_, _, 1) => Ok(Format::Od),
+            (_, None, _) | (_, _, 2) => Ok(Format::Hexdump),
+            (_, _, 3) => Ok(Format::Wireshark),
+            _ => Err(ParseError::new(
+                index,
+                digits,
+                ParseErrorKind::UnknownFormat,
+            )),
+        }
+    }
+
+    /// Returns the part of `columns` holding the hex digits, `columns`
+    /// being what follows the offset and its separator.
+    fn hex_columns(self, columns: &[u8]) -> &[u8] {
+        let end = match self {
+            Format::Hexdump => columns.iter().position(|&byte| byte == b'|'),
+            Format::Xxd | Format::Od => find(columns, b"  "),
+            Format::Wireshark => find(columns, b"   "),
+        };
+        &columns[..end.unwrap_or(columns.len())]
+    }
+}
+
+/// Options for [`parse_with`].
+///
+/// The default detects the format from the first line and returns at most
+/// [`DEFAULT_MAX_LEN`] bytes, like [`parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    format: Option<Format>,
+    max_len: usize,
+}
+
+impl ParseOptions {
+    /// Creates the default options.
+    pub const fn new() -> Self {
+        Self {
+            format: None,
+            max_len: DEFAULT_MAX_LEN,
+        }
+    }
+
+    /// Reads the dump as `format` instead of detecting it.
+    pub const fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Fails with [`ParseErrorKind::TooLong`] rather than return more than
+    /// `max_len` bytes.
+    pub const fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An error in a dump, at a 1-based line and column of the input.
+pub type ParseError = crate::ParseError<ParseErrorKind>;
+
+/// The kind of a [`ParseError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A line does not start with a hex offset, or a `*` line is not
+    /// followed by one.
+    MissingOffset,
+    /// A line does not start where the lines before it end.
+    OffsetMismatch { expected: usize, found: usize },
+    /// The hex columns hold a byte that is not a hex digit.
+    InvalidChar { byte: u8 },
+    /// A group of hex digits has an odd length.
+    OddLength,
+    /// The first line does not look like any of the [`Format`]s.
+    UnknownFormat,
+    /// The dump holds more than [`ParseOptions::max_len`] bytes.
+    TooLong { max_len: usize },
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseErrorKind::MissingOffset => f.write_str("missing offset"),
+            ParseErrorKind::OffsetMismatch { expected, found } => {
+                write!(f, "expected offset {expected:#x}, found {found:#x}")
+            }
+            ParseErrorKind::InvalidChar { byte } => {
+                write!(f, "invalid hex character '{}'", byte.escape_ascii())
+            }
+            ParseErrorKind::OddLength => {
+                f.write_str("odd number of hex digits")
+            }
+            ParseErrorKind::UnknownFormat => {
+                f.write_str("unrecognized dump format")
+            }
+            ParseErrorKind::TooLong { max_len } => {
+                write!(f, "dump holds more than {max_len} bytes")
+            }
+        }
+    }
+}
+
+/// Reads a dump in any of the [`Format`]s back into bytes, detecting the
+/// format from the first line.
+///
+/// The data starts at the offset of the first line, and every line must
+/// start where the one before it ends. A `*` line repeats the line before
+/// it up to the offset of the next line, as `hexdump`, `od` and `xxd -a`
+/// squeeze repeated lines. At most [`DEFAULT_MAX_LEN`] bytes are returned,
+/// see [`parse_with`] to read longer dumps.
+///
+/// ```
+/// let dump = "\
+/// 00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 2e 0a        |Hello, world..|
+/// 0000000e
+/// ";
+/// assert_eq!(muhex::dump::parse(dump)?, b"Hello, world.\n");
+/// # Ok::<(), muhex::dump::ParseError>(())
+/// ```
+#[inline]
+pub fn parse<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, ParseError> {
+    parse_with(input, ParseOptions::new())
+}
+
+/// Like [`parse`], but reads `input` according to `options`.
+///
+/// ```
+/// use muhex::dump::{self, Format, ParseErrorKind, ParseOptions};
+///
+/// let options = ParseOptions::new().format(Format::Wireshark);
+/// let dump = "0000   de ad be ef   ....\n";
+/// assert_eq!(dump::parse_with(dump, options)?, [0xde, 0xad, 0xbe, 0xef]);
+///
+/// let dump = "0000   de ad bg";
+/// let err = dump::parse_with(dump, options).unwrap_err();
+/// assert_eq!((err.line, err.column), (1, 15));
+/// assert_eq!(err.kind, ParseErrorKind::InvalidChar { byte: b'g' });
+/// # Ok::<(), dump::ParseError>(())
+/// ```
+pub fn parse_with<T: AsRef<[u8]>>(
+    input: T,
+    options: ParseOptions,
+) -> Result<Vec<u8>, ParseError> {
+    let mut parser = Parser::new(options);
+    let mut output = Vec::new();
+
+    for (index, line) in lines(input.as_ref()) {
+        parser.line(index, line, |bytes| {
+            output.extend_from_slice(bytes);
+            Ok::<_, ParseError>(())
+        })?;
+    }
+
+    parser.finish()?;
+    Ok(output)
+}
+
+/// Like [`parse_with`], but reads the dump from `input` a line at a time
+/// and writes the bytes to `out` as it goes, so that memory use does not
+/// grow with the dump. Errors in the dump come back as
+/// [`InvalidData`](std::io::ErrorKind::InvalidData) errors holding the
+/// [`ParseError`].
+///
+/// ```
+/// use muhex::dump::{self, ParseOptions};
+///
+/// let dump = "00000000  41 42 |AB|\n*\n00000006  43 |C|\n";
+/// let mut out = Vec::new();
+/// dump::parse_io(dump.as_bytes(), &mut out, ParseOptions::new())?;
+/// assert_eq!(out, b"ABABABC");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_io<R, W>(
+    mut input: R,
+    out: &mut W,
+    options: ParseOptions,
+) -> std::io::Result<()>
+where
+    R: std::io::BufRead,
+    W: std::io::Write + ?Sized,
+{
+    let mut parser = Parser::new(options);
+    let mut buf = Vec::new();
+
+    for index in 0.. {
+        buf.clear();
+        if input.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        let line = buf.trim_ascii();
+        if !line.is_empty() {
+            parser.line(index, line, |bytes| out.write_all(bytes))?;
+        }
+    }
+
+    Ok(parser.finish()?)
+}
+
+/// Reads a dump a line at a time, for [`parse_with`] and [`parse_io`].
+struct Parser {
+    format: Option<Format>,
+    max_len: usize,
+    /// The offset of the first line.
+    start: Option<usize>,
+    /// Bytes read so far.
+    len: usize,
+    /// The bytes of the last line.
+    last: Vec<u8>,
+    /// The index of a `*` line after the last line.
+    squeeze: Option<usize>,
+}
+
+impl Parser {
+    fn new(options: ParseOptions) -> Self {
+        Self {
+            format: options.format,
+            max_len: options.max_len,
+            start: None,
+            len: 0,
+            last: Vec::new(),
+            squeeze: None,
+        }
+    }
+
+    /// Reads the non-blank `line` at `index`, without surrounding
+    /// whitespace, passing the bytes it holds to `emit`.
+    fn line<E: From<ParseError>>(
+        &mut self,
+        index: usize,
+        line: &[u8],
+        mut emit: impl FnMut(&[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        if line == b"*" {
+            self.squeeze.get_or_insert(index);
+            return Ok(());
+        }
+
+        let format = match self.format {
+            Some(format) => format,
+            None => *self.format.insert(Format::detect(index, line)?),
+        };
+        let max_len = self.max_len;
+        let digits = offset_len(line);
+        let offset = parse_offset(&line[..digits]).ok_or(ParseError::new(
+            index,
+            0,
+            ParseErrorKind::MissingOffset,
+        ))?;
+
+        let mut column = digits;
+        if format == Format::Xxd {
+            if line.get(column) != Some(&b':') {
+                let kind = ParseErrorKind::MissingOffset;
+                return Err(ParseError::new(index, column, kind).into());
+            }
+            column += 1;
+        }
+        if !matches!(line.get(column), None | Some(b' ' | b'\t')) {
+            let kind = ParseErrorKind::MissingOffset;
+            return Err(ParseError::new(index, column, kind).into());
+        }
+
+        let start = *self.start.get_or_insert(offset);
+        let expected = start + self.len;
+        if self.squeeze.take().is_some() && !self.last.is_empty() {
+            // Only whole copies of the last line fit before the offset
+            let gap = offset.saturating_sub(expected);
+            let rest = gap % self.last.len();
+            if rest != 0 {
+                let kind = ParseErrorKind::OffsetMismatch {
+                    expected: offset - rest,
+                    found: offset,
+                };
+                return Err(ParseError::new(index, 0, kind).into());
+            }
+            if gap > max_len - self.len {
+                let kind = ParseErrorKind::TooLong { max_len };
+                return Err(ParseError::new(index, 0, kind).into());
+            }
+            for _ in 0..gap / self.last.len() {
+                emit(&self.last)?;
+            }
+            self.len += gap;
+        }
+        let expected = start + self.len;
+        if offset != expected {
+            let kind = ParseErrorKind::OffsetMismatch {
+                expected,
+                found: offset,
+            };
+            return Err(ParseError::new(index, 0, kind).into());
+        }
+
+        while line
+            .get(column)
+            .is_some_and(|&byte| byte == b' ' || byte == b'\t')
+        {
+            column += 1;
+        }
+        let hex = format.hex_columns(&line[column..]);
+        self.last.clear();
+        decode_columns(hex, &mut self.last)
+            .map_err(|(at, kind)| ParseError::new(index, column + at, kind))?;
+        if self.last.len() > max_len - self.len {
+            let kind = ParseErrorKind::TooLong { max_len };
+            return Err(ParseError::new(index, column, kind).into());
+        }
+        self.len += self.last.len();
+        emit(&self.last)
+    }
+
+    /// Checks that the dump did not end on a `*` line.
+    fn finish(&self) -> Result<(), ParseError> {
+        match self.squeeze {
+            Some(index) => {
+                Err(ParseError::new(index, 0, ParseErrorKind::MissingOffset))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Decodes the space-separated groups of digits in `hex` onto `output`,
+/// or returns the index in `hex` and kind of the first error.
+fn decode_columns(
+    hex: &[u8],
+    output: &mut Vec<u8>,
+) -> Result<(), (usize, ParseErrorKind)> {
+    let mut at = 0;
+
+    for group in hex.split(|&byte| byte == b' ' || byte == b'\t') {
+        output.reserve(group.len() / 2);
+        let dst = &mut output.spare_capacity_mut()[..group.len() / 2];
+        decode_into(group, dst, Case::Any).map_err(|err| match err {
+            Error::InvalidChar { byte, index } => {
+                (at + index, ParseErrorKind::InvalidChar { byte })
+            }
+            _ => (at, ParseErrorKind::OddLength),
+        })?;
+        unsafe { output.set_len(output.len() + group.len() / 2) };
+        at += group.len() + 1;
+    }
+
+    Ok(())
+}
+
+/// Returns the number of hex digits at the start of `line`.
+fn offset_len(line: &[u8]) -> usize {
+    line.iter()
+        .take_while(|byte| byte.is_ascii_hexdigit())
+        .count()
+}
+
+/// Parses hex `digits` as an offset, or returns `None` if there are none
+/// or the offset does not fit in a `usize`.
+fn parse_offset(digits: &[u8]) -> Option<usize> {
+    if digits.is_empty() {
+        return None;
+    }
+
+    digits.iter().try_fold(0usize, |offset, &byte| {
+        let digit = (byte as char).to_digit(16)? as usize;
+        offset.checked_mul(16)?.checked_add(digit)
+    })
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::{
+        DEFAULT_MAX_LEN,
+        Format,
+        ParseErrorKind,
+        ParseOptions,
+        parse,
+        parse_with,
+    };
+    use crate::{
+        dump::{DumpOptions, to_string},
+        parse::tests::error,
+    };
+
+    /// Data made of a few distinct lines, so that some repeat and get
+    /// squeezed.
+    fn repetitive() -> impl Strategy<Value = Vec<u8>> {
+        vec(prop_oneof![Just([0u8; 16]), any::<[u8; 16]>()], 0..8)
+            .prop_flat_map(|lines| {
+                let len = lines.len() * 16;
+                (Just(lines.concat()), 0..=len)
+            })
+            .prop_map(|(mut data, len)| {
+                data.truncate(len);
+                data
+            })
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_parse_roundtrip(
+        #[strategy(repetitive())] input: Vec<u8>,
+        #[strategy(1usize..40)] width: usize,
+        #[strategy(1usize..8)] group: usize,
+        #[strategy(0usize..10)] offset_width: usize,
+        squeeze: bool,
+        ascii: bool,
+    ) {
+        let hexdump = DumpOptions::hexdump();
+        prop_assert_eq!(&parse(to_string(&input, hexdump))?, &input);
+
+        let xxd = DumpOptions::xxd()
+            .bytes_per_line(width)
+            .group_size(group)
+            .squeeze(squeeze)
+            .ascii(ascii)
+            .upper(ascii);
+        prop_assert_eq!(&parse(to_string(&input, xxd))?, &input);
+
+        let hexdump = hexdump
+            .bytes_per_line(width)
+            .group_size(group)
+            .offset_width(offset_width);
+        let dump = to_string(&input, hexdump.squeeze(squeeze).ascii(ascii));
+        prop_assert_eq!(&parse(&dump)?, &input);
+        let options = ParseOptions::new().format(Format::Hexdump);
+        prop_assert_eq!(&parse_with(dump, options)?, &input);
+
+        let xxd = xxd.offset_width(offset_width);
+        prop_assert_eq!(&parse(to_string(&input, xxd))?, &input);
+    }
+
+    #[cfg(feature = "std")]
+    #[test_strategy::proptest(cases = 500)]
+    fn test_parse_io(
+        #[strategy(repetitive())] input: Vec<u8>,
+        #[strategy(1usize..40)] capacity: usize,
+    ) {
+        use std::io::{BufReader, ErrorKind};
+
+        use super::parse_io;
+
+        let options = ParseOptions::new();
+        let dump = to_string(&input, DumpOptions::hexdump());
+        let reader = BufReader::with_capacity(capacity, dump.as_bytes());
+        let mut output = Vec::new();
+        parse_io(reader, &mut output, options)?;
+        prop_assert_eq!(&output, &input);
+
+        let dump = dump.replace('0', "x");
+        let err = parse_io(dump.as_bytes(), &mut Vec::new(), options);
+        let err = err
+            .err()
+            .map(|err| (err.kind(), err.into_inner().unwrap().to_string()));
+        let expected = parse(&dump)
+            .err()
+            .map(|err| (ErrorKind::InvalidData, err.to_string()));
+        prop_assert_eq!(err, expected);
+    }
+
+    #[test]
+    fn test_parse_od() {
+        // Output of `od -Ax -tx1z`, and without the `z` for the gutter
+        let dump = "\
+000000 48 65 6c 6c 6f 2c 20 77 6f 72 6c 64 2e 0a 00 00  >Hello, world....<
+000010 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  >................<
+*
+000030 00 00 00 00 00 00 61 62 63                       >......abc<
+000039
+";
+        let mut expected = b"Hello, world.\n".to_vec();
+        expected.extend_from_slice(&[0; 40]);
+        expected.extend_from_slice(b"abc");
+
+        assert_eq!(parse(dump), Ok(expected.clone()));
+        let plain: String = dump
+            .lines()
+            .map(|line| format!("{}\n", line.split("  >").next().unwrap()))
+            .collect();
+        assert_eq!(parse(plain), Ok(expected));
+    }
+
+    #[test]
+    fn test_parse_wireshark() {
+        let dump = "\
+0000   00 1a 2b 3c 4d 5e 00 11 22 33 44 55 08 00 45 00   ..+<M^..\"3DU..E.
+0010   00 3c 1c 46 40 00                                 .<.F@.
+";
+        assert_eq!(
+            parse(dump),
+            Ok(vec![
+                0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e, 0x00, 0x11, 0x22, 0x33,
+                0x44, 0x55, 0x08, 0x00, 0x45, 0x00, 0x00, 0x3c, 0x1c, 0x46,
+                0x40, 0x00,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse("00000000: 4865 6c6c  Hell\n00000005: 6f  o\n"),
+            Err(error(
+                2,
+                1,
+                ParseErrorKind::OffsetMismatch {
+                    expected: 4,
+                    found: 5
+                }
+            ))
+        );
+        assert_eq!(
+            parse("00000000: 4865 6x6c  Hell\n"),
+            Err(error(1, 17, ParseErrorKind::InvalidChar { byte: b'x' }))
+        );
+        assert_eq!(
+            parse("00000000: 4865 6c6  Hel\n"),
+            Err(error(1, 16, ParseErrorKind::OddLength))
+        );
+        assert_eq!(
+            parse("00000000  00 00\n*\n"),
+            Err(error(2, 1, ParseErrorKind::MissingOffset))
+        );
+        assert_eq!(
+            parse("hello\n"),
+            Err(error(1, 1, ParseErrorKind::MissingOffset))
+        );
+        assert_eq!(
+            parse_with(
+                "00000000  00 00\n",
+                ParseOptions::new().format(Format::Xxd)
+            ),
+            Err(error(1, 9, ParseErrorKind::MissingOffset))
+        );
+        assert_eq!(
+            error(3, 7, ParseErrorKind::InvalidChar { byte: b'\n' })
+                .to_string(),
+            "line 3, column 7: invalid hex character '\\n'"
+        );
+        assert_eq!(parse(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_parse_squeeze_limits() {
+        // A squeeze up to the end of the address space must not allocate
+        assert_eq!(
+            parse("00000000  41 |A|\n*\nffffffff  41 |A|\n"),
+            Err(error(
+                3,
+                1,
+                ParseErrorKind::TooLong {
+                    max_len: DEFAULT_MAX_LEN
+                }
+            ))
+        );
+        assert_eq!(
+            parse("00000000  41 42 |AB|\n*\n00000007  41 42 |AB|\n"),
+            Err(error(
+                3,
+                1,
+                ParseErrorKind::OffsetMismatch {
+                    expected: 6,
+                    found: 7
+                }
+            ))
+        );
+
+        let dump = "00000000  41 42 |AB|\n*\n00000006  41 |A|\n";
+        assert_eq!(parse(dump), Ok(b"ABABABA".to_vec()));
+        assert_eq!(
+            parse_with(dump, ParseOptions::new().max_len(6)),
+            Err(error(3, 11, ParseErrorKind::TooLong { max_len: 6 }))
+        );
+        assert_eq!(
+            parse_with(dump, ParseOptions::new().max_len(5)),
+            Err(error(3, 1, ParseErrorKind::TooLong { max_len: 5 }))
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use core::fmt;
+
+/// The error type for hex encoding and decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input contains a byte that is not a hex digit. `index` is the
+    /// offset of the first such byte in the hex input.
+    InvalidChar { byte: u8, index: usize },
+    /// The hex input has an odd number of characters.
+    OddLength,
+    /// The output buffer does not have the length the input requires.
+    InvalidOutputLength { expected: usize, actual: usize },
+    /// A `0x` prefix or `\x` escape is missing where one is required.
+    /// `index` is the offset in the input where it was expected.
+    MissingPrefix { index: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Error::InvalidChar { byte, index } => write!(
+                f,
+                "invalid hex character '{}' at index {index}",
+                byte.escape_ascii()
+            ),
+            Error::OddLength => f.write_str("hex string length must be even"),
+            Error::InvalidOutputLength { expected, actual } => write!(
+                f,
+                "output slice has wrong length: expected {expected}, got {actual}"
+            ),
+            Error::MissingPrefix { index } => {
+                write!(f, "missing hex prefix at index {index}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err {
+            Error::InvalidChar { .. } | Error::MissingPrefix { .. } => {
+                std::io::ErrorKind::InvalidData
+            }
+            Error::OddLength | Error::InvalidOutputLength { .. } => {
+                std::io::ErrorKind::InvalidInput
+            }
+        };
+
+        std::io::Error::new(kind, err)
+    }
+}
@@ -0,0 +1,69 @@
+// Errors shared by the firmware formats, Intel HEX and S-records.
+
+use core::fmt;
+
+/// An error in a firmware file, at a 1-based line and column of the input.
+pub type ParseError = crate::ParseError<ParseErrorKind>;
+
+/// The kind of a [`ParseError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+    /// A record does not start with its start code, such as `:`.
+    MissingStartCode,
+    /// A record holds a byte that is not a hex digit.
+    InvalidChar { byte: u8 },
+    /// A record has an odd number of hex digits.
+    OddLength,
+    /// The length of a record does not match its byte count.
+    InvalidLength,
+    /// The checksum of a record does not match its contents.
+    ChecksumMismatch { expected: u8, found: u8 },
+    /// A record has a type the format does not define.
+    UnknownRecordType { record_type: u8 },
+    /// A record does not have the length its type requires.
+    MalformedRecord,
+    /// A record holds data past the end of the 32-bit address space.
+    AddressOverflow,
+    /// The input ends without an end-of-file or termination record.
+    MissingEndOfFile,
+    /// A count record does not match the number of data records before it.
+    RecordCountMismatch { expected: u32, found: u32 },
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseErrorKind::MissingStartCode => {
+                f.write_str("record does not start with a start code")
+            }
+            ParseErrorKind::InvalidChar { byte } => {
+                write!(f, "invalid hex character '{}'", byte.escape_ascii())
+            }
+            ParseErrorKind::OddLength => {
+                f.write_str("odd number of hex digits")
+            }
+            ParseErrorKind::InvalidLength => {
+                f.write_str("record length does not match its byte count")
+            }
+            ParseErrorKind::ChecksumMismatch { expected, found } => write!(
+                f,
+                "checksum mismatch: expected {expected:02X}, found {found:02X}"
+            ),
+            ParseErrorKind::UnknownRecordType { record_type } => {
+                write!(f, "unknown record type {record_type:02X}")
+            }
+            ParseErrorKind::MalformedRecord => f.write_str("malformed record"),
+            ParseErrorKind::AddressOverflow => {
+                f.write_str("data past the end of the address space")
+            }
+            ParseErrorKind::MissingEndOfFile => {
+                f.write_str("missing end-of-file record")
+            }
+            ParseErrorKind::RecordCountMismatch { expected, found } => write!(
+                f,
+                "record count mismatch: expected {expected}, found {found}"
+            ),
+        }
+    }
+}
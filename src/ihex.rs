@@ -0,0 +1,525 @@
+//! Intel HEX files, as used to flash microcontrollers.
+//!
+//! ```
+//! let file = "\
+//! :0400000001020304F2
+//! :00000001FF
+//! ";
+//! let image = muhex::ihex::parse(file)?;
+//! assert_eq!(image.get(0x0002), Some(3));
+//! assert_eq!(muhex::ihex::to_string(&image), file);
+//! # Ok::<(), muhex::ihex::ParseError>(())
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str};
+
+pub use crate::firmware::{ParseError, ParseErrorKind};
+use crate::{
+    Buf,
+    Case,
+    Error,
+    HEX_ENCODE_LUT_UPPER,
+    decode_into,
+    encode_to_buf_with_lut,
+    image::MemoryImage,
+    parse::lines,
+};
+
+/// Most data bytes a record can hold.
+pub const MAX_DATA_LEN: usize = 255;
+
+/// Bytes of a record besides its data: the byte count, the address, the
+/// record type and the checksum.
+const OVERHEAD: usize = 5;
+
+/// A record of an Intel HEX file, one line without its start code.
+///
+/// Records display as the line they are written as, and [`Record::parse`]
+/// reads one back:
+///
+/// ```
+/// use muhex::ihex::Record;
+///
+/// let record = Record::ExtendedLinearAddress(0x0800);
+/// assert_eq!(record.to_string(), ":020000040800F2");
+/// assert_eq!(Record::parse(":020000040800F2")?, record);
+/// # Ok::<(), muhex::ihex::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Record {
+    /// Type `00`: up to [`MAX_DATA_LEN`] bytes at `offset` from the base
+    /// address.
+    Data { offset: u16, data: Vec<u8> },
+    /// Type `01`: the end of the file.
+    EndOfFile,
+    /// Type `02`: sets the base address to the segment times 16.
+    ExtendedSegmentAddress(u16),
+    /// Type `03`: the 8086 `CS:IP` execution starts at.
+    StartSegmentAddress { cs: u16, ip: u16 },
+    /// Type `04`: sets the upper 16 bits of the base address.
+    ExtendedLinearAddress(u16),
+    /// Type `05`: the 32-bit address execution starts at.
+    StartLinearAddress(u32),
+}
+
+impl Record {
+    /// Parses a single record, ignoring surrounding whitespace.
+    #[inline]
+    pub fn parse<T: AsRef<[u8]>>(line: T) -> Result<Self, ParseError> {
+        let mut buf = [0; MAX_DATA_LEN + OVERHEAD];
+        let (record_type, offset, data) =
+            decode_record(line.as_ref().trim_ascii(), 0, &mut buf)?;
+        Self::from_fields(record_type, offset, data, 0)
+    }
+
+    fn from_fields(
+        record_type: u8,
+        offset: u16,
+        data: &[u8],
+        index: usize,
+    ) -> Result<Self, ParseError> {
+        let record = match (record_type, data) {
+            (0x00, _) => Record::Data {
+                offset,
+                data: data.to_vec(),
+            },
+            (0x01, []) => Record::EndOfFile,
+            (0x02, &[hi, lo]) => {
+                Record::ExtendedSegmentAddress(u16::from_be_bytes([hi, lo]))
+            }
+            (0x03, &[cs_hi, cs_lo, ip_hi, ip_lo]) => {
+                Record::StartSegmentAddress {
+                    cs: u16::from_be_bytes([cs_hi, cs_lo]),
+                    ip: u16::from_be_bytes([ip_hi, ip_lo]),
+                }
+            }
+            (0x04, &[hi, lo]) => {
+                Record::ExtendedLinearAddress(u16::from_be_bytes([hi, lo]))
+            }
+            (0x05, &[a, b, c, d]) => {
+                Record::StartLinearAddress(u32::from_be_bytes([a, b, c, d]))
+            }
+            (0x00..=0x05, _) => {
+                let kind = ParseErrorKind::MalformedRecord;
+                return Err(ParseError::new(index, 0, kind));
+            }
+            _ => {
+                // The type follows the start code, byte count and address
+                let kind = ParseErrorKind::UnknownRecordType { record_type };
+                return Err(ParseError::new(index, 7, kind));
+            }
+        };
+
+        Ok(record)
+    }
+}
+
+impl fmt::Display for Record {
+    /// # Panics
+    ///
+    /// Panics if a data record holds more than [`MAX_DATA_LEN`] bytes.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut payload = [0; 4];
+        let (record_type, offset, data): (u8, u16, &[u8]) = match *self {
+            Record::Data { offset, ref data } => (0x00, offset, data),
+            Record::EndOfFile => (0x01, 0, &[]),
+            Record::ExtendedSegmentAddress(segment) => {
+                payload[..2].copy_from_slice(&segment.to_be_bytes());
+                (0x02, 0, &payload[..2])
+            }
+            Record::StartSegmentAddress { cs, ip } => {
+                payload[..2].copy_from_slice(&cs.to_be_bytes());
+                payload[2..].copy_from_slice(&ip.to_be_bytes());
+                (0x03, 0, &payload)
+            }
+            Record::ExtendedLinearAddress(upper) => {
+                payload[..2].copy_from_slice(&upper.to_be_bytes());
+                (0x04, 0, &payload[..2])
+            }
+            Record::StartLinearAddress(address) => {
+                payload = address.to_be_bytes();
+                (0x05, 0, &payload)
+            }
+        };
+
+        write_record(f, record_type, offset, data)
+    }
+}
+
+/// Writes a record of `record_type` holding `data`, without a newline.
+fn write_record<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    record_type: u8,
+    offset: u16,
+    data: &[u8],
+) -> fmt::Result {
+    assert!(
+        data.len() <= MAX_DATA_LEN,
+        "record data must be at most 255 bytes"
+    );
+
+    let mut bytes = [0; MAX_DATA_LEN + OVERHEAD];
+    let len = data.len() + OVERHEAD;
+    let [hi, lo] = offset.to_be_bytes();
+    bytes[..4].copy_from_slice(&[data.len() as u8, hi, lo, record_type]);
+    bytes[4..len - 1].copy_from_slice(data);
+    bytes[len - 1] = checksum(&bytes[..len - 1]);
+
+    let mut line = [b':'; 1 + (MAX_DATA_LEN + OVERHEAD) * 2];
+    let hex = &mut line[1..1 + len * 2];
+    encode_to_buf_with_lut(&bytes[..len], hex, &HEX_ENCODE_LUT_UPPER)
+        .expect("Len of the line is always correct");
+    // SAFETY: the start code and hex digits are ASCII
+    out.write_str(unsafe { str::from_utf8_unchecked(&line[..1 + len * 2]) })
+}
+
+/// Returns the two's complement of the sum of `bytes`, which makes the sum
+/// of a record and its checksum 0.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+        .wrapping_neg()
+}
+
+/// Decodes the record on the line at `index` into `buf`, verifying its
+/// length and checksum, and returns its type, address and data.
+fn decode_record<'a>(
+    line: &[u8],
+    index: usize,
+    buf: &'a mut [u8; MAX_DATA_LEN + OVERHEAD],
+) -> Result<(u8, u16, &'a [u8]), ParseError> {
+    let error = |column, kind| ParseError::new(index, column, kind);
+
+    let Some(hex) = line.strip_prefix(b":") else {
+        return Err(error(0, ParseErrorKind::MissingStartCode));
+    };
+    let Some(bytes) = buf.get_mut(..hex.len() / 2) else {
+        return Err(error(0, ParseErrorKind::InvalidLength));
+    };
+    // SAFETY: `decode_into` only writes initialized bytes
+    decode_into(hex, unsafe { bytes.dst() }, Case::Any).map_err(
+        |err| match err {
+            Error::InvalidChar { byte, index } => {
+                error(1 + index, ParseErrorKind::InvalidChar { byte })
+            }
+            _ => error(0, ParseErrorKind::OddLength),
+        },
+    )?;
+
+    let bytes = &*bytes;
+    if bytes.len() < OVERHEAD || bytes.len() != bytes[0] as usize + OVERHEAD {
+        return Err(error(0, ParseErrorKind::InvalidLength));
+    }
+    let (&found, fields) =
+        bytes.split_last().expect("Records always hold a checksum");
+    let expected = checksum(fields);
+    if found != expected {
+        let kind = ParseErrorKind::ChecksumMismatch { expected, found };
+        return Err(error(line.len() - 2, kind));
+    }
+
+    let offset = u16::from_be_bytes([fields[1], fields[2]]);
+    Ok((fields[3], offset, &fields[4..]))
+}
+
+/// Iterates over the records of an Intel HEX file, skipping blank lines.
+///
+/// ```
+/// use muhex::ihex::{self, Record};
+///
+/// let records: Vec<_> = ihex::records(":0100100042AD\n:00000001FF\n")
+///     .collect::<Result<_, _>>()?;
+/// assert_eq!(
+///     records,
+///     [Record::Data { offset: 0x10, data: vec![0x42] }, Record::EndOfFile]
+/// );
+/// # Ok::<(), muhex::ihex::ParseError>(())
+/// ```
+#[inline]
+pub fn records<T: AsRef<[u8]> + ?Sized>(
+    input: &T,
+) -> impl Iterator<Item = Result<Record, ParseError>> {
+    lines(input.as_ref()).map(|(index, line)| {
+        let mut buf = [0; MAX_DATA_LEN + OVERHEAD];
+        let (record_type, offset, data) = decode_record(line, index, &mut buf)?;
+        Record::from_fields(record_type, offset, data, index)
+    })
+}
+
+/// Reads an Intel HEX file into a [`MemoryImage`], up to its end-of-file
+/// record.
+///
+/// After an extended segment address, data addresses are
+/// `segment * 16 + offset` with the offset wrapping at 64 KiB, so a record
+/// running past offset `FFFF` continues at the start of the segment. A start
+/// segment address `CS:IP` becomes the entry point `CS * 16 + IP`, which
+/// [`write()`] writes back as a start linear address.
+#[inline]
+pub fn parse<T: AsRef<[u8]>>(input: T) -> Result<MemoryImage, ParseError> {
+    let mut image = MemoryImage::new();
+    let mut base = 0u32;
+    let mut segmented = false;
+    let mut end = 0;
+    let mut buf = [0; MAX_DATA_LEN + OVERHEAD];
+
+    for (index, line) in lines(input.as_ref()) {
+        let (record_type, offset, data) = decode_record(line, index, &mut buf)?;
+
+        // Data records are by far the most common, so skip building them
+        if record_type == 0x00 {
+            if segmented {
+                // Offsets wrap within the 64 KiB segment, as they would in
+                // the IP register
+                let (head, tail) =
+                    data.split_at(data.len().min(0x1_0000 - offset as usize));
+                image.insert(base + offset as u32, head);
+                image.insert(base, tail);
+            } else {
+                let address = base as u64 + offset as u64;
+                if address + data.len() as u64 > 1 << 32 {
+                    let kind = ParseErrorKind::AddressOverflow;
+                    return Err(ParseError::new(index, 3, kind));
+                }
+                image.insert(address as u32, data);
+            }
+            end = index + 1;
+            continue;
+        }
+
+        match Record::from_fields(record_type, offset, data, index)? {
+            Record::Data { .. } => {
+                unreachable!("data records are handled above")
+            }
+            Record::EndOfFile => return Ok(image),
+            Record::ExtendedSegmentAddress(segment) => {
+                base = (segment as u32) << 4;
+                segmented = true;
+            }
+            Record::StartSegmentAddress { cs, ip } => {
+                image.set_entry_point(Some(((cs as u32) << 4) + ip as u32));
+            }
+            Record::ExtendedLinearAddress(upper) => {
+                base = (upper as u32) << 16;
+                segmented = false;
+            }
+            Record::StartLinearAddress(address) => {
+                image.set_entry_point(Some(address));
+            }
+        }
+        end = index + 1;
+    }
+
+    Err(ParseError::new(end, 0, ParseErrorKind::MissingEndOfFile))
+}
+
+/// Writes `image` as an Intel HEX file to `out`, with up to `record_len`
+/// bytes per data record.
+///
+/// Extended linear address records are written where the upper 16 bits of
+/// the address change, and the entry point, if any, as a start linear
+/// address record before the end-of-file record. A file read with a start
+/// segment address record (type `03`) is therefore written back with a
+/// start linear address record (type `05`) for the same physical address,
+/// as the image does not keep the `CS:IP` split.
+///
+/// # Panics
+///
+/// Panics if `record_len` is 0 or above [`MAX_DATA_LEN`].
+pub fn write<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    image: &MemoryImage,
+    record_len: usize,
+) -> fmt::Result {
+    assert!(
+        (1..=MAX_DATA_LEN).contains(&record_len),
+        "record length must be between 1 and 255"
+    );
+    let mut upper = 0;
+
+    for (start, mut bytes) in image.segments() {
+        let mut address = start;
+
+        while !bytes.is_empty() {
+            let high = (address >> 16) as u16;
+            if high != upper {
+                writeln!(out, "{}", Record::ExtendedLinearAddress(high))?;
+                upper = high;
+            }
+
+            // Records must not cross into the next 64 KiB
+            let offset = address as u16;
+            let len =
+                record_len.min(bytes.len()).min(0x10000 - offset as usize);
+            let (data, rest) = bytes.split_at(len);
+            write_record(out, 0x00, offset, data)?;
+            out.write_char('\n')?;
+
+            bytes = rest;
+            address = address.wrapping_add(len as u32);
+        }
+    }
+
+    if let Some(entry_point) = image.entry_point() {
+        writeln!(out, "{}", Record::StartLinearAddress(entry_point))?;
+    }
+    writeln!(out, "{}", Record::EndOfFile)
+}
+
+/// Returns `image` as an Intel HEX file with 16 bytes per data record, see
+/// [`write()`].
+#[inline]
+pub fn to_string(image: &MemoryImage) -> String {
+    let mut out = String::new();
+    write(&mut out, image, 16).expect("Writing to a String never fails");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::{ParseErrorKind, Record, parse, records, to_string, write};
+    use crate::{image::MemoryImage, parse::tests::error};
+
+    /// Writes spread over a few 64 KiB pages, including the last one.
+    fn memory_image() -> impl Strategy<Value = MemoryImage> {
+        let address = prop_oneof![
+            any::<u32>().prop_map(|address| address & 0x0003_ffff),
+            (0u32..0x200).prop_map(|address| u32::MAX - address),
+        ];
+        let writes = vec((address, vec(any::<u8>(), 0..300)), 0..10);
+        (writes, any::<Option<u32>>()).prop_map(|(writes, entry_point)| {
+            let mut image = MemoryImage::new();
+            for (address, mut data) in writes {
+                data.truncate((u32::MAX - address) as usize + 1);
+                image.insert(address, &data);
+            }
+            image.set_entry_point(entry_point);
+            image
+        })
+    }
+
+    #[test_strategy::proptest(cases = 300)]
+    fn test_ihex_roundtrip(
+        #[strategy(memory_image())] image: MemoryImage,
+        #[strategy(1usize..=255)] record_len: usize,
+    ) {
+        prop_assert_eq!(&parse(to_string(&image))?, &image);
+
+        let mut file = String::new();
+        write(&mut file, &image, record_len)?;
+        prop_assert_eq!(&parse(&file)?, &image);
+        for record in records(&file) {
+            let record = record?;
+            prop_assert_eq!(&Record::parse(record.to_string())?, &record);
+        }
+    }
+
+    #[test]
+    fn test_ihex_example() {
+        let file = "\
+:10010000214601360121470136007EFE09D2190140
+:100110002146017E17C20001FF5F16002148011928
+:10012000194E79234623965778239EDA3F01B2CAA7
+:100130003F0156702B5E712B722B732146013421C7
+:00000001FF
+";
+        let image = parse(file).unwrap();
+        let segments: Vec<_> = image.segments().collect();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].0, 0x0100);
+        assert_eq!(segments[0].1.len(), 64);
+        assert_eq!(to_string(&image), file);
+    }
+
+    #[test]
+    fn test_ihex_addresses() {
+        let file = "\
+:020000021000EC
+:0100000042BD
+:020000040800F2
+:0100100043AC
+:0400000312345678E5
+:00000001FF
+";
+        let image = parse(file).unwrap();
+        assert_eq!(image.get(0x1_0000), Some(0x42));
+        assert_eq!(image.get(0x0800_0010), Some(0x43));
+        assert_eq!(image.entry_point(), Some((0x1234 << 4) + 0x5678));
+    }
+
+    #[test]
+    fn test_ihex_start_segment_address() {
+        // `CS:IP` 1234:5678 comes back as the linear address 0x179B8
+        let image = parse(":0400000312345678E5\n:00000001FF\n").unwrap();
+        assert_eq!(image.entry_point(), Some(0x179b8));
+        assert_eq!(to_string(&image), ":04000005000179B8C5\n:00000001FF\n");
+    }
+
+    #[test]
+    fn test_ihex_segment_wrap() {
+        let file = "\
+:020000021000EC
+:04FFFE0001020304F5
+:00000001FF
+";
+        let image = parse(file).unwrap();
+        assert_eq!(
+            image.segments().collect::<Vec<_>>(),
+            [(0x1_0000, &[3, 4][..]), (0x1_fffe, &[1, 2][..])]
+        );
+    }
+
+    #[test]
+    fn test_ihex_errors() {
+        assert_eq!(
+            parse(":0100000042BE\n:00000001FF\n"),
+            Err(error(
+                1,
+                12,
+                ParseErrorKind::ChecksumMismatch {
+                    expected: 0xbd,
+                    found: 0xbe
+                }
+            ))
+        );
+        assert_eq!(
+            parse("\n:01000000x2BD\n"),
+            Err(error(2, 10, ParseErrorKind::InvalidChar { byte: b'x' }))
+        );
+        assert_eq!(
+            parse(":0100000042BD\n"),
+            Err(error(2, 1, ParseErrorKind::MissingEndOfFile))
+        );
+        assert_eq!(
+            parse("0100000042BD\n"),
+            Err(error(1, 1, ParseErrorKind::MissingStartCode))
+        );
+        assert_eq!(
+            parse(":0200000042BD\n"),
+            Err(error(1, 1, ParseErrorKind::InvalidLength))
+        );
+        assert_eq!(
+            parse(":0100000042B\n"),
+            Err(error(1, 1, ParseErrorKind::OddLength))
+        );
+        assert_eq!(
+            Record::parse(":0000000AF6"),
+            Err(error(
+                1,
+                8,
+                ParseErrorKind::UnknownRecordType { record_type: 0x0a }
+            ))
+        );
+        assert_eq!(
+            Record::parse(":0100000400FB"),
+            Err(error(1, 1, ParseErrorKind::MalformedRecord))
+        );
+        assert_eq!(
+            parse(":02000004FFFFFC\n:02FFFF000102FD\n:00000001FF\n"),
+            Err(error(2, 4, ParseErrorKind::AddressOverflow))
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Sparse memory images, as read from and written to firmware files by the
+//! [`ihex`](crate::ihex) and [`srec`](crate::srec) modules.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::ops::Bound;
+
+/// The bytes of a firmware file at their addresses in a 32-bit address
+/// space, with the gaps between them left out.
+///
+/// Writes that touch or overlap are merged into a single segment, the
+/// later write winning where they overlap.
+///
+/// ```
+/// use muhex::image::MemoryImage;
+///
+/// let mut image = MemoryImage::new();
+/// image.insert(0x0800_0000, &[1, 2, 3, 4]);
+/// image.insert(0x0800_0004, &[5, 6]);
+/// image.insert(0x2000_0000, &[7]);
+///
+/// let segments: Vec<_> = image.segments().collect();
+/// assert_eq!(
+///     segments,
+///     [(0x0800_0000, &[1, 2, 3, 4, 5, 6][..]), (0x2000_0000, &[7][..])]
+/// );
+/// assert_eq!(image.get(0x0800_0005), Some(6));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MemoryImage {
+    segments: BTreeMap<u32, Vec<u8>>,
+    entry_point: Option<u32>,
+}
+
+impl MemoryImage {
+    /// Creates an empty image.
+    pub const fn new() -> Self {
+        Self {
+            segments: BTreeMap::new(),
+            entry_point: None,
+        }
+    }
+
+    /// Writes `data` at `address`, over any bytes already there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` does not end within the 32-bit address space.
+    pub fn insert(&mut self, address: u32, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let end = address as u64 + data.len() as u64;
+        assert!(end <= 1 << 32, "data must end within the address space");
+
+        // Extend the segment the data starts in or right after, so that
+        // writing a file front to back appends to a single segment
+        let (start, mut merged) =
+            match self.segments.range(..=address).next_back() {
+                Some((&start, bytes))
+                    if start as u64 + bytes.len() as u64 >= address as u64 =>
+                {
+                    (start, self.segments.remove(&start).unwrap_or_default())
+                }
+                _ => (address, Vec::new()),
+            };
+
+        // Then merge the segments the data overlaps or runs up to
+        let last = end.min(u32::MAX as u64) as u32;
+        let after = (Bound::Excluded(address), Bound::Included(last));
+        while let Some((&other, _)) = self.segments.range(after).next() {
+            let bytes = self.segments.remove(&other).unwrap_or_default();
+            copy_at(&mut merged, (other - start) as usize, &bytes);
+        }
+        copy_at(&mut merged, (address - start) as usize, data);
+
+        self.segments.insert(start, merged);
+    }
+
+    /// Returns the byte at `address`, if the image holds one.
+    pub fn get(&self, address: u32) -> Option<u8> {
+        let (start, bytes) = self.segments.range(..=address).next_back()?;
+        bytes.get((address - start) as usize).copied()
+    }
+
+    /// Iterates over the contiguous runs of bytes in the image, with their
+    /// start addresses, in order of address.
+    pub fn segments(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        self.segments
+            .iter()
+            .map(|(&start, bytes)| (start, bytes.as_slice()))
+    }
+
+    /// Returns the number of bytes the image holds, not counting gaps.
+    pub fn len(&self) -> usize {
+        self.segments.values().map(Vec::len).sum()
+    }
+
+    /// Returns true if the image holds no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the address execution starts at, if the file gave one.
+    ///
+    /// This is a 32-bit linear address: an Intel HEX start segment address
+    /// `CS:IP` is stored as `CS * 16 + IP`, and not told apart from a start
+    /// linear address when the image is written again.
+    pub fn entry_point(&self) -> Option<u32> {
+        self.entry_point
+    }
+
+    /// Sets the address execution starts at.
+    pub fn set_entry_point(&mut self, entry_point: Option<u32>) {
+        self.entry_point = entry_point;
+    }
+
+    /// Returns the lowest address in the image and the bytes from there to
+    /// the end of the last segment, with `fill` in the gaps, as for writing
+    /// a raw binary.
+    ///
+    /// ```
+    /// use muhex::image::MemoryImage;
+    ///
+    /// let mut image = MemoryImage::new();
+    /// image.insert(0x100, &[1, 2]);
+    /// image.insert(0x104, &[3]);
+    /// assert_eq!(image.flatten(0xff), (0x100, vec![1, 2, 0xff, 0xff, 3]));
+    /// ```
+    pub fn flatten(&self, fill: u8) -> (u32, Vec<u8>) {
+        let Some(&base) = self.segments.keys().next() else {
+            return (0, Vec::new());
+        };
+
+        let mut flat = Vec::new();
+        for (start, bytes) in self.segments() {
+            flat.resize((start - base) as usize, fill);
+            flat.extend_from_slice(bytes);
+        }
+        (base, flat)
+    }
+}
+
+/// Writes `bytes` at `offset` in `buf`, growing it with zeros as needed.
+fn copy_at(buf: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+    let end = offset + bytes.len();
+    if buf.len() < end {
+        buf.resize(end, 0);
+    }
+    buf[offset..end].copy_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::MemoryImage;
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_image_insert(
+        #[strategy(vec((0u32..300, vec(any::<u8>(), 0..40)), 0..20))]
+        writes: Vec<(u32, Vec<u8>)>,
+    ) {
+        let mut image = MemoryImage::new();
+        let mut memory = [None; 340];
+        for (address, data) in &writes {
+            image.insert(*address, data);
+            for (i, &byte) in data.iter().enumerate() {
+                memory[*address as usize + i] = Some(byte);
+            }
+        }
+
+        for (address, &byte) in memory.iter().enumerate() {
+            prop_assert_eq!(image.get(address as u32), byte);
+        }
+        prop_assert_eq!(image.len(), memory.iter().flatten().count());
+
+        // Segments are maximal runs, so they neither touch nor overlap
+        let segments: Vec<_> = image.segments().collect();
+        for pair in segments.windows(2) {
+            prop_assert!(
+                pair[0].0 as usize + pair[0].1.len() < pair[1].0 as usize
+            );
+        }
+    }
+
+    #[test]
+    fn test_image_edges() {
+        let mut image = MemoryImage::new();
+        image.insert(u32::MAX, &[1]);
+        image.insert(u32::MAX - 2, &[2, 3]);
+        assert_eq!(
+            image.segments().collect::<Vec<_>>(),
+            [(u32::MAX - 2, &[2, 3, 1][..])]
+        );
+        assert_eq!(image.flatten(0), (u32::MAX - 2, vec![2, 3, 1]));
+        assert_eq!(MemoryImage::new().flatten(0), (0, Vec::new()));
+    }
+}
@@ -0,0 +1,533 @@
+// Streaming adapters over `std::io`. The buffering lives in `EncodeBuf` and
+// `DecodeBuf` so the async adapters can share it.
+
+use core::{
+    mem::{self, ManuallyDrop},
+    ptr,
+};
+use std::{
+    boxed::Box,
+    io::{self, Read, Write},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    Buf,
+    Case,
+    Error,
+    HEX_ENCODE_LUT,
+    HEX_ENCODE_LUT_UPPER,
+    decode_into,
+    encode_to_buf_with_lut,
+};
+
+/// Capacity of the internal hex buffers, matching `std::io::BufWriter`.
+pub(crate) const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+// ─── Buffering ──────────────────────────────────────────────────────────
+
+/// Hex waiting to be written to an inner writer.
+pub(crate) struct EncodeBuf {
+    buf: Vec<u8>,
+    /// Number of bytes at the front of `buf` already written out.
+    written: usize,
+    lut: &'static [u8; 16],
+}
+
+impl EncodeBuf {
+    /// Creates a buffer for `capacity` hex characters, at least 2.
+    pub(crate) fn new(capacity: usize, upper: bool) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity.max(2)),
+            written: 0,
+            lut: match upper {
+                false => &HEX_ENCODE_LUT,
+                true => &HEX_ENCODE_LUT_UPPER,
+            },
+        }
+    }
+
+    /// Whether the buffer should be written out before encoding `len` more
+    /// bytes: either nothing fits, or it is in the way of encoding all of
+    /// them in one go.
+    pub(crate) fn should_flush(&self, len: usize) -> bool {
+        let spare = self.buf.capacity() - self.buf.len();
+        spare < 2 || (spare < len * 2 && !self.pending().is_empty())
+    }
+
+    /// Encodes as much of `data` as fits, returning how much that was.
+    pub(crate) fn encode(&mut self, data: &[u8]) -> usize {
+        if self.pending().is_empty() {
+            self.buf.clear();
+            self.written = 0;
+        }
+
+        let spare = self.buf.capacity() - self.buf.len();
+        let n = data.len().min(spare / 2);
+        encode_to_buf_with_lut(
+            &data[..n],
+            &mut self.buf.spare_capacity_mut()[..n * 2],
+            self.lut,
+        )
+        .expect("Len of the spare capacity is always correct");
+        // SAFETY: the first `n * 2` spare bytes were just initialized
+        unsafe { self.buf.set_len(self.buf.len() + n * 2) };
+
+        n
+    }
+
+    /// Hex that still has to be written out.
+    pub(crate) fn pending(&self) -> &[u8] {
+        &self.buf[self.written..]
+    }
+
+    /// Marks the first `n` pending bytes as written out.
+    pub(crate) fn consume(&mut self, n: usize) {
+        self.written += n;
+    }
+}
+
+/// Hex read from an inner reader and waiting to be decoded.
+pub(crate) struct DecodeBuf {
+    buf: Box<[u8]>,
+    /// Range of `buf` holding hex that was read but not decoded yet.
+    start: usize,
+    end: usize,
+    /// Stream offset of `buf[0]`.
+    offset: usize,
+}
+
+impl DecodeBuf {
+    /// Creates a buffer for `capacity` hex characters, at least 2.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            buf: vec![0; capacity.max(2)].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            offset: 0,
+        }
+    }
+
+    /// Whether at least one byte can be decoded without reading more.
+    pub(crate) fn has_pair(&self) -> bool {
+        self.end - self.start >= 2
+    }
+
+    /// Returns the space to read more hex into, keeping the odd nibble, if
+    /// any, at the front of the buffer. Only call this without a full pair
+    /// buffered, so the space is never empty.
+    pub(crate) fn spare(&mut self) -> &mut [u8] {
+        self.buf.copy_within(self.start..self.end, 0);
+        self.offset += self.start;
+        self.end -= self.start;
+        self.start = 0;
+
+        &mut self.buf[self.end..]
+    }
+
+    /// Marks `n` bytes of [`spare`](Self::spare) as read.
+    pub(crate) fn fill(&mut self, n: usize) {
+        self.end += n;
+    }
+
+    /// Decodes as many buffered pairs as fit into `out`, reporting invalid
+    /// characters at their offset in the whole stream.
+    pub(crate) fn decode<Dst>(&mut self, out: &mut Dst) -> Result<usize, Error>
+    where
+        Dst: Buf + ?Sized,
+    {
+        // SAFETY: `decode_into` only writes fully initialized bytes
+        let out = unsafe { out.dst() };
+        let n = out.len().min((self.end - self.start) / 2);
+        let hex = &self.buf[self.start..self.start + n * 2];
+
+        decode_into(hex, &mut out[..n], Case::Any).map_err(
+            |err| match err {
+                Error::InvalidChar { byte, index } => Error::InvalidChar {
+                    byte,
+                    index: self.offset + self.start + index,
+                },
+                err => err,
+            },
+        )?;
+
+        self.start += n * 2;
+        Ok(n)
+    }
+
+    /// Checks that the stream did not end on an odd nibble.
+    pub(crate) fn finish(&self) -> Result<(), Error> {
+        match self.end - self.start {
+            0 => Ok(()),
+            _ => Err(Error::OddLength),
+        }
+    }
+}
+
+// ─── Blocking adapters ──────────────────────────────────────────────────
+
+/// A writer that hex-encodes everything written to it into an inner writer.
+///
+/// Input is encoded in chunks straight into an internal buffer, which is
+/// written out once full, on [`flush`](Write::flush) or when the
+/// `HexWriter` is dropped. As with `BufWriter`, errors while writing out
+/// the buffer on drop are ignored, so call `flush` to observe them.
+///
+/// ```
+/// use std::io::{self, Write};
+///
+/// use muhex::HexWriter;
+///
+/// let mut writer = HexWriter::new(Vec::new());
+/// io::copy(&mut &b"Hello world!"[..], &mut writer)?;
+/// assert_eq!(writer.into_inner()?, b"48656c6c6f20776f726c6421");
+/// # Ok::<(), io::Error>(())
+/// ```
+pub struct HexWriter<W: Write> {
+    inner: W,
+    buf: EncodeBuf,
+}
+
+impl<W: Write> HexWriter<W> {
+    /// Creates a `HexWriter` that writes lowercase hex to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates a `HexWriter` that writes uppercase hex to `inner`.
+    pub fn new_upper(inner: W) -> Self {
+        Self::with_capacity_upper(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates a `HexWriter` whose internal buffer holds `capacity` hex
+    /// characters, rounded up to at least 2.
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: EncodeBuf::new(capacity, false),
+        }
+    }
+
+    /// Like [`with_capacity`](Self::with_capacity), but writes uppercase
+    /// hex.
+    pub fn with_capacity_upper(capacity: usize, inner: W) -> Self {
+        Self {
+            inner,
+            buf: EncodeBuf::new(capacity, true),
+        }
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    ///
+    /// Writing to it directly may interleave with hex that is still
+    /// buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes out the buffered hex and returns the inner writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush_buf()?;
+
+        // Moving `inner` out would run into our `Drop` impl, so skip it
+        let mut this = ManuallyDrop::new(self);
+        drop(mem::take(&mut this.buf.buf));
+        // SAFETY: `this` is never dropped, so `inner` is moved out only once
+        Ok(unsafe { ptr::read(&this.inner) })
+    }
+
+    /// Writes the buffered hex to the inner writer, keeping whatever could
+    /// not be written on error.
+    fn flush_buf(&mut self) -> io::Result<()> {
+        while !self.buf.pending().is_empty() {
+            match self.inner.write(self.buf.pending()) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => self.buf.consume(n),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HexWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.should_flush(data.len()) {
+            self.flush_buf()?;
+        }
+
+        Ok(self.buf.encode(data))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for HexWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush_buf();
+    }
+}
+
+/// A reader that decodes the hex read from an inner reader.
+///
+/// Hex is read in chunks of any size into an internal buffer; a trailing
+/// odd nibble is kept until the next chunk arrives. Invalid characters are
+/// reported as [`io::ErrorKind::InvalidData`] wrapping an
+/// [`Error::InvalidChar`] whose index is the offset in the whole stream,
+/// and a stream ending on an odd nibble as [`Error::OddLength`].
+///
+/// ```
+/// use std::io::{self, Read};
+///
+/// use muhex::HexReader;
+///
+/// let mut reader = HexReader::new(&b"48656c6c6f20776f726c6421"[..]);
+/// let mut decoded = String::new();
+/// reader.read_to_string(&mut decoded)?;
+/// assert_eq!(decoded, "Hello world!");
+/// # Ok::<(), io::Error>(())
+/// ```
+pub struct HexReader<R: Read> {
+    inner: R,
+    buf: DecodeBuf,
+}
+
+impl<R: Read> HexReader<R> {
+    /// Creates a `HexReader` that decodes the hex read from `inner`.
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates a `HexReader` whose internal buffer holds `capacity` hex
+    /// characters, rounded up to at least 2.
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        Self {
+            inner,
+            buf: DecodeBuf::new(capacity),
+        }
+    }
+
+    /// Returns a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner reader.
+    ///
+    /// Reading from it directly skips over hex that is still buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the inner reader, discarding any buffered hex.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for HexReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        while !self.buf.has_pair() {
+            match self.inner.read(self.buf.spare()) {
+                Ok(0) => {
+                    return self.buf.finish().map(|()| 0).map_err(Into::into);
+                }
+                Ok(n) => self.buf.fill(n),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(self.buf.decode(out)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+
+    use proptest::prelude::*;
+
+    use super::{EncodeBuf, HexReader, HexWriter};
+    use crate::Error;
+
+    /// A writer that accepts at most `limit` bytes per call.
+    struct Trickle {
+        out: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            let n = data.len().min(self.limit);
+            self.out.extend_from_slice(&data[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A reader that returns at most `limit` bytes per call.
+    struct Drip<'a> {
+        data: &'a [u8],
+        limit: usize,
+    }
+
+    impl Read for Drip<'_> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = out.len().min(self.limit).min(self.data.len());
+            out[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    fn muhex_error(err: &io::Error) -> Option<&Error> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_hex_writer_chunked(
+        input: Vec<u8>,
+        #[strategy(1usize..300)] chunk: usize,
+        #[strategy(2usize..300)] capacity: usize,
+        #[strategy(1usize..300)] limit: usize,
+        upper: bool,
+    ) {
+        let inner = Trickle {
+            out: Vec::new(),
+            limit,
+        };
+        let mut writer = HexWriter {
+            inner,
+            buf: EncodeBuf::new(capacity, upper),
+        };
+        for part in input.chunks(chunk) {
+            writer.write_all(part)?;
+        }
+
+        let expected = match upper {
+            false => hex::encode(&input),
+            true => hex::encode_upper(&input),
+        };
+        prop_assert_eq!(writer.into_inner()?.out, expected.into_bytes());
+    }
+
+    #[test_strategy::proptest(cases = 100)]
+    fn test_hex_writer_io_copy(input: Vec<u8>) {
+        let mut writer = HexWriter::new_upper(Vec::new());
+        io::copy(&mut input.as_slice(), &mut writer)?;
+        writer.flush()?;
+        let expected = hex::encode_upper(&input).into_bytes();
+        prop_assert_eq!(writer.get_ref(), &expected);
+    }
+
+    #[test]
+    fn test_hex_writer_with_capacity_upper() {
+        let mut writer = HexWriter::with_capacity_upper(2, Vec::new());
+        writer.write_all(b"\xab\xcd\xef").unwrap();
+        assert_eq!(writer.into_inner().unwrap(), b"ABCDEF");
+    }
+
+    #[test]
+    fn test_hex_writer_flushes_on_drop() {
+        let mut out = Vec::new();
+        HexWriter::new(&mut out).write_all(b"\x01\xff").unwrap();
+        assert_eq!(out, b"01ff");
+    }
+
+    #[test]
+    fn test_hex_writer_write_zero() {
+        let inner = Trickle {
+            out: Vec::new(),
+            limit: 0,
+        };
+        let mut writer = HexWriter::with_capacity(4, inner);
+        writer.write_all(b"\x01\x02").unwrap();
+        let err = writer.write_all(b"\x03").expect_err("err");
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(writer.get_ref().out, b"");
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_hex_reader_chunked(
+        input: Vec<u8>,
+        #[strategy(1usize..300)] chunk: usize,
+        #[strategy(2usize..300)] capacity: usize,
+        #[strategy(1usize..300)] limit: usize,
+    ) {
+        let encoded = hex::encode_upper(&input);
+        let inner = Drip {
+            data: encoded.as_bytes(),
+            limit,
+        };
+        let mut reader = HexReader::with_capacity(capacity, inner);
+
+        let mut output = Vec::new();
+        let mut part = vec![0u8; chunk];
+        loop {
+            match reader.read(&mut part)? {
+                0 => break,
+                n => output.extend_from_slice(&part[..n]),
+            }
+        }
+        prop_assert_eq!(output, input);
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_hex_reader_reports_stream_offset(
+        #[strategy(proptest::collection::vec(any::<u8>(), 1..2000))] input: Vec<
+            u8,
+        >,
+        at: usize,
+        #[strategy(1usize..100)] limit: usize,
+    ) {
+        let mut encoded = hex::encode(&input).into_bytes();
+        let at = at % encoded.len();
+        encoded[at] = b'x';
+
+        let inner = Drip {
+            data: &encoded,
+            limit,
+        };
+        let mut reader = HexReader::with_capacity(64, inner);
+        let err = reader.read_to_end(&mut Vec::new()).expect_err("err");
+
+        prop_assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        prop_assert_eq!(
+            muhex_error(&err),
+            Some(&Error::InvalidChar {
+                byte: b'x',
+                index: at
+            })
+        );
+    }
+
+    #[test]
+    fn test_hex_reader_odd_length() {
+        let mut reader = HexReader::new(&b"abc"[..]);
+        let mut output = Vec::new();
+        let err = reader.read_to_end(&mut output).expect_err("err");
+
+        assert_eq!(output, [0xab]);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(muhex_error(&err), Some(&Error::OddLength));
+    }
+}
@@ -1,941 +1,1002 @@
-#[cfg(target_arch = "x86_64")]
-use std::arch::x86_64::*;
-use std::{
-    io::{Error, ErrorKind},
-    mem::MaybeUninit,
-};
-
-#[cfg(not(target_arch = "x86_64"))]
-use std::simd::{
-    Select, Simd, cmp::SimdPartialOrd, simd_swizzle, u8x16, u8x32, u8x64,
-};
-
-#[cfg(not(target_arch = "x86_64"))]
-type SimdU8<const LANES: usize> = Simd<u8, LANES>;
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(
+    all(
+        feature = "portable-simd",
+        any(test, not(all(target_arch = "x86_64", target_feature = "sse2")))
+    ),
+    feature(portable_simd)
+)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::{mem::MaybeUninit, str};
 
 #[cfg(feature = "serde")]
 pub mod serde;
 
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod async_io;
 mod buf;
-
+mod display;
+pub mod dump;
+mod error;
+#[cfg(feature = "alloc")]
+mod firmware;
+#[cfg(feature = "alloc")]
+pub mod ihex;
+#[cfg(feature = "alloc")]
+pub mod image;
+#[cfg(feature = "std")]
+mod io;
+mod options;
+#[cfg(feature = "alloc")]
+mod parse;
+#[cfg(all(
+    feature = "portable-simd",
+    any(test, not(all(target_arch = "x86_64", target_feature = "sse2")))
+))]
+mod portable;
+#[cfg(feature = "alloc")]
+mod prefix;
+#[cfg(feature = "alloc")]
+pub mod srec;
+#[cfg(any(
+    test,
+    all(
+        not(all(target_arch = "x86_64", target_feature = "sse2")),
+        not(feature = "portable-simd")
+    )
+))]
+mod swar;
+mod traits;
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod x86;
+
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+pub use async_io::{AsyncHexReader, AsyncHexWriter};
 pub use buf::*;
+pub use display::{HexDisplay, display};
+pub use error::Error;
+#[cfg(feature = "std")]
+pub use io::{HexReader, HexWriter};
+pub use options::{Case, DecodeOptions, EncodeOptions, IgnoreSet, Prefix};
+#[cfg(feature = "alloc")]
+pub use parse::ParseError;
+// Soft-float x86_64 targets (kernels, UEFI) cannot run the SSE kernels, so
+// they get the same fallback as other architectures
+#[cfg(all(
+    not(all(target_arch = "x86_64", target_feature = "sse2")),
+    feature = "portable-simd"
+))]
+use portable as backend;
+#[cfg(feature = "alloc")]
+pub use prefix::{
+    decode_escaped,
+    decode_prefixed,
+    encode_escaped,
+    encode_prefixed,
+};
+#[cfg(all(
+    not(all(target_arch = "x86_64", target_feature = "sse2")),
+    not(feature = "portable-simd")
+))]
+use swar as backend;
+pub use traits::*;
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+use x86 as backend;
 
 const HEX_ENCODE_LUT: [u8; 16] = *b"0123456789abcdef";
+const HEX_ENCODE_LUT_UPPER: [u8; 16] = *b"0123456789ABCDEF";
 
-#[cfg(target_arch = "x86_64")]
-#[repr(align(16))]
-struct Aligned16([u8; 16]);
+// ─── Encode: scalar fallback ────────────────────────────────────────────
 
-#[cfg(target_arch = "x86_64")]
-static HEX_ENCODE_LUT_ALIGNED: Aligned16 = Aligned16(HEX_ENCODE_LUT);
+#[inline(always)]
+fn encode_scalar(data: &[u8], result: &mut [MaybeUninit<u8>], lut: &[u8; 16]) {
+    for (i, byte) in data.iter().enumerate() {
+        let hi = (byte >> 4) as usize;
+        let lo = (byte & 0xf) as usize;
+        result[i * 2].write(lut[hi]);
+        result[i * 2 + 1].write(lut[lo]);
+    }
+}
 
-// ─── Encode: x86_64 PSHUFB-based fast paths ────────────────────────────
+// ─── Encode: public API ─────────────────────────────────────────────────
 
-#[cfg(target_arch = "x86_64")]
-#[inline(always)]
-unsafe fn encode_simd_64(input: *const u8, output: *mut MaybeUninit<u8>) {
-    // Process 64 input bytes → 128 output bytes using AVX-512
-    unsafe {
-        let raw = _mm512_loadu_si512(input.cast());
-        let mask = _mm512_set1_epi8(0x0F);
-        let lut = _mm512_broadcast_i32x4(_mm_load_si128(
-            HEX_ENCODE_LUT_ALIGNED.0.as_ptr().cast(),
-        ));
-
-        // Extract nibbles
-        let hi = _mm512_and_si512(_mm512_srli_epi16(raw, 4), mask);
-        let lo = _mm512_and_si512(raw, mask);
-
-        // LUT lookup: single vpshufb per nibble set
-        let hi_ascii = _mm512_shuffle_epi8(lut, hi);
-        let lo_ascii = _mm512_shuffle_epi8(lut, lo);
-
-        // Interleave hi[i], lo[i] → output[2i], output[2i+1]
-        // using vpermi2b (AVX-512 VBMI cross-lane byte permute)
-        //
-        // First 64 output bytes: hi[0],lo[0],hi[1],lo[1],...,hi[31],lo[31]
-        // Second 64 output bytes: hi[32],lo[32],...,hi[63],lo[63]
-        //
-        // vpermi2b uses 7-bit indices: bit 6 selects source (0=first, 1=second)
-        let perm_lo = _mm512_set_epi8(
-            // Bytes 63..0 (set_epi8 is high-to-low)
-            95, 31, 94, 30, 93, 29, 92, 28, 91, 27, 90, 26, 89, 25, 88, 24, 87,
-            23, 86, 22, 85, 21, 84, 20, 83, 19, 82, 18, 81, 17, 80, 16, 79, 15,
-            78, 14, 77, 13, 76, 12, 75, 11, 74, 10, 73, 9, 72, 8, 71, 7, 70, 6,
-            69, 5, 68, 4, 67, 3, 66, 2, 65, 1, 64, 0,
-        );
-        let perm_hi = _mm512_set_epi8(
-            127, 63, 126, 62, 125, 61, 124, 60, 123, 59, 122, 58, 121, 57, 120,
-            56, 119, 55, 118, 54, 117, 53, 116, 52, 115, 51, 114, 50, 113, 49,
-            112, 48, 111, 47, 110, 46, 109, 45, 108, 44, 107, 43, 106, 42, 105,
-            41, 104, 40, 103, 39, 102, 38, 101, 37, 100, 36, 99, 35, 98, 34,
-            97, 33, 96, 32,
-        );
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn encode<T: AsRef<[u8]>>(v: T) -> String {
+    encode_with_lut(v.as_ref(), &HEX_ENCODE_LUT)
+}
 
-        let mut out_lo = perm_lo;
-        let mut out_hi = perm_hi;
-        out_lo = _mm512_permutex2var_epi8(hi_ascii, out_lo, lo_ascii);
-        out_hi = _mm512_permutex2var_epi8(hi_ascii, out_hi, lo_ascii);
+/// Like [`encode`], but emits uppercase hex digits.
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn encode_upper<T: AsRef<[u8]>>(v: T) -> String {
+    encode_with_lut(v.as_ref(), &HEX_ENCODE_LUT_UPPER)
+}
 
-        _mm512_storeu_si512(output.cast(), out_lo);
-        _mm512_storeu_si512(output.add(64).cast(), out_hi);
-    }
+#[inline]
+pub fn encode_to_buf<T, Dst>(v: T, dst: &mut Dst) -> Result<(), Error>
+where
+    T: AsRef<[u8]>,
+    Dst: Buf + ?Sized,
+{
+    encode_to_buf_with_lut(v.as_ref(), dst, &HEX_ENCODE_LUT)
 }
 
-#[cfg(target_arch = "x86_64")]
+/// Like [`encode_to_buf`], but emits uppercase hex digits.
+#[inline]
+pub fn encode_upper_to_buf<T, Dst>(v: T, dst: &mut Dst) -> Result<(), Error>
+where
+    T: AsRef<[u8]>,
+    Dst: Buf + ?Sized,
+{
+    encode_to_buf_with_lut(v.as_ref(), dst, &HEX_ENCODE_LUT_UPPER)
+}
+
+#[cfg(feature = "alloc")]
 #[inline(always)]
-unsafe fn encode_simd_32(input: *const u8, output: *mut MaybeUninit<u8>) {
+fn encode_with_lut(data: &[u8], lut: &[u8; 16]) -> String {
+    let mut result = Vec::with_capacity(data.len() * 2);
+    encode_to_buf_with_lut(data, result.spare_capacity_mut(), lut)
+        .expect("Len of result is always correct");
     unsafe {
-        let raw = _mm256_loadu_si256(input.cast());
-        let mask = _mm256_set1_epi8(0x0F);
-        let lut = _mm256_broadcastsi128_si256(_mm_load_si128(
-            HEX_ENCODE_LUT_ALIGNED.0.as_ptr().cast(),
-        ));
-
-        // Extract nibbles
-        let hi = _mm256_and_si256(_mm256_srli_epi16(raw, 4), mask);
-        let lo = _mm256_and_si256(raw, mask);
-
-        // LUT lookup: single vpshufb per nibble set
-        let hi_ascii = _mm256_shuffle_epi8(lut, hi);
-        let lo_ascii = _mm256_shuffle_epi8(lut, lo);
-
-        // Interleave using unpack + lane fix
-        let interleaved_lo = _mm256_unpacklo_epi8(hi_ascii, lo_ascii);
-        let interleaved_hi = _mm256_unpackhi_epi8(hi_ascii, lo_ascii);
-        let final_lo =
-            _mm256_permute2x128_si256(interleaved_lo, interleaved_hi, 0x20);
-        let final_hi =
-            _mm256_permute2x128_si256(interleaved_lo, interleaved_hi, 0x31);
-
-        _mm256_storeu_si256(output.cast(), final_lo);
-        _mm256_storeu_si256(output.add(32).cast(), final_hi);
+        result.set_len(data.len() * 2);
     }
+    unsafe { String::from_utf8_unchecked(result) }
 }
 
-#[cfg(target_arch = "x86_64")]
 #[inline(always)]
-unsafe fn encode_simd_16(input: *const u8, output: *mut MaybeUninit<u8>) {
-    unsafe {
-        let raw = _mm_loadu_si128(input.cast());
-        let mask = _mm_set1_epi8(0x0F);
-        let lut = _mm_load_si128(HEX_ENCODE_LUT_ALIGNED.0.as_ptr().cast());
-
-        let hi = _mm_and_si128(_mm_srli_epi16(raw, 4), mask);
-        let lo = _mm_and_si128(raw, mask);
-
-        let hi_ascii = _mm_shuffle_epi8(lut, hi);
-        let lo_ascii = _mm_shuffle_epi8(lut, lo);
-
-        let interleaved_lo = _mm_unpacklo_epi8(hi_ascii, lo_ascii);
-        let interleaved_hi = _mm_unpackhi_epi8(hi_ascii, lo_ascii);
-
-        _mm_storeu_si128(output.cast(), interleaved_lo);
-        _mm_storeu_si128(output.add(16).cast(), interleaved_hi);
+fn encode_to_buf_with_lut<Dst>(
+    data: &[u8],
+    dst: &mut Dst,
+    lut: &[u8; 16],
+) -> Result<(), Error>
+where
+    Dst: Buf + ?Sized,
+{
+    let expected_len = data.len() * 2;
+    // SAFETY: We only write fully initialized bytes through backend::encode
+    let dst = unsafe { dst.dst() };
+    if dst.len() != expected_len {
+        return Err(Error::InvalidOutputLength {
+            expected: expected_len,
+            actual: dst.len(),
+        });
     }
-}
 
-// ─── Encode: portable_simd fallback for non-x86 ────────────────────────
+    backend::encode(data, dst, lut);
 
-#[cfg(not(target_arch = "x86_64"))]
-#[inline(always)]
-fn encode_simd_32(input: &[u8], output: &mut [MaybeUninit<u8>]) {
-    let raw: u8x32 = Simd::from_slice(input);
-
-    let high_nibble = raw >> Simd::splat(4);
-    let low_nibble = raw & Simd::splat(0x0F);
-
-    let bias_0 = Simd::splat(b'0');
-    let bias_a = Simd::splat(b'a' - 10);
-    let cmp_9 = Simd::splat(9u8);
-
-    let hi_ascii = nibble_to_ascii_32(high_nibble, bias_0, bias_a, cmp_9);
-    let lo_ascii = nibble_to_ascii_32(low_nibble, bias_0, bias_a, cmp_9);
-
-    let interleaved: u8x64 = simd_swizzle!(
-        hi_ascii,
-        lo_ascii,
-        [
-            0, 32, 1, 33, 2, 34, 3, 35, 4, 36, 5, 37, 6, 38, 7, 39, 8, 40, 9,
-            41, 10, 42, 11, 43, 12, 44, 13, 45, 14, 46, 15, 47, 16, 48, 17, 49,
-            18, 50, 19, 51, 20, 52, 21, 53, 22, 54, 23, 55, 24, 56, 25, 57, 26,
-            58, 27, 59, 28, 60, 29, 61, 30, 62, 31, 63
-        ]
-    );
-
-    let interleaved: &[u8; 64] = interleaved.as_array();
-    let uninit_src: &[MaybeUninit<u8>; 64] =
-        unsafe { std::mem::transmute(interleaved) };
-    output.copy_from_slice(uninit_src);
+    Ok(())
 }
 
-#[cfg(not(target_arch = "x86_64"))]
-#[inline(always)]
-fn encode_simd_16(input: &[u8], output: &mut [MaybeUninit<u8>]) {
-    let raw: u8x16 = Simd::from_slice(input);
-
-    let high_nibble = raw >> Simd::splat(4);
-    let low_nibble = raw & Simd::splat(0x0F);
-
-    let bias_0 = Simd::splat(b'0');
-    let bias_a = Simd::splat(b'a' - 10);
-    let cmp_9 = Simd::splat(9u8);
-
-    let hi_ascii = nibble_to_ascii(high_nibble, bias_0, bias_a, cmp_9);
-    let lo_ascii = nibble_to_ascii(low_nibble, bias_0, bias_a, cmp_9);
-
-    let interleaved: u8x32 = simd_swizzle!(
-        hi_ascii,
-        lo_ascii,
-        [
-            0, 16, 1, 17, 2, 18, 3, 19, 4, 20, 5, 21, 6, 22, 7, 23, 8, 24, 9,
-            25, 10, 26, 11, 27, 12, 28, 13, 29, 14, 30, 15, 31
-        ]
-    );
-
-    let interleaved: &[u8; 32] = interleaved.as_array();
-    let uninit_src: &[MaybeUninit<u8>; 32] =
-        unsafe { std::mem::transmute(interleaved) };
-    output.copy_from_slice(uninit_src);
-}
+// ─── Encode: in place ───────────────────────────────────────────────────
+
+/// Bytes encoded or decoded per round of [`encode_in_place_slice`] and
+/// [`decode_in_place`].
+const IN_PLACE_BLOCK_LEN: usize = 512;
+
+/// Encodes the bytes in the front half of `buf` into hex filling all of
+/// it, returning the hex, so data can be encoded without a second buffer.
+///
+/// Blocks are encoded from the tail toward the head, each copied to the
+/// stack first, so the hex for block `i` at `buf[i * 2 * n..]` only
+/// overwrites bytes that were already encoded. Fails with
+/// [`Error::OddLength`] if `buf` has an odd length.
+///
+/// ```
+/// let mut buf = [0xc0, 0xff, 0xee, 0, 0, 0];
+/// assert_eq!(muhex::encode_in_place_slice(&mut buf)?, "c0ffee");
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[inline]
+pub fn encode_in_place_slice(buf: &mut [u8]) -> Result<&mut str, Error> {
+    if buf.len() % 2 != 0 {
+        return Err(Error::OddLength);
+    }
 
-#[cfg(not(target_arch = "x86_64"))]
-#[inline(always)]
-fn nibble_to_ascii(
-    n: u8x16,
-    bias_0: u8x16,
-    bias_a: u8x16,
-    cmp_9: u8x16,
-) -> u8x16 {
-    let mask_gt_9 = n.simd_gt(cmp_9);
-    let base_0 = n + bias_0;
-    let base_a = n + bias_a;
-    mask_gt_9.select(base_a, base_0)
-}
+    let mut bytes = [0u8; IN_PLACE_BLOCK_LEN];
+    let mut end = buf.len() / 2;
+
+    while end > 0 {
+        let start = end.saturating_sub(IN_PLACE_BLOCK_LEN);
+        let bytes = &mut bytes[..end - start];
+        bytes.copy_from_slice(&buf[start..end]);
+        encode_to_buf_with_lut(
+            bytes,
+            &mut buf[start * 2..end * 2],
+            &HEX_ENCODE_LUT,
+        )
+        .expect("Len of the block is always correct");
+        end = start;
+    }
 
-#[cfg(not(target_arch = "x86_64"))]
-#[inline(always)]
-fn nibble_to_ascii_32(
-    n: u8x32,
-    bias_0: u8x32,
-    bias_a: u8x32,
-    cmp_9: u8x32,
-) -> u8x32 {
-    let mask_gt_9 = n.simd_gt(cmp_9);
-    let base_0 = n + bias_0;
-    let base_a = n + bias_a;
-    mask_gt_9.select(base_a, base_0)
+    // SAFETY: hex digits are ASCII
+    Ok(unsafe { str::from_utf8_unchecked_mut(buf) })
 }
 
-// ─── Encode: scalar fallback ────────────────────────────────────────────
+/// Replaces the bytes in `buf` with their hex, growing it to twice its
+/// length, and returns the hex. The allocation is reused if it has spare
+/// capacity for the hex, see [`encode_in_place_slice`].
+///
+/// ```
+/// let mut buf = Vec::with_capacity(8);
+/// buf.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+/// assert_eq!(muhex::encode_in_place(&mut buf), "deadbeef");
+/// assert_eq!(buf, b"deadbeef");
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn encode_in_place(buf: &mut Vec<u8>) -> &mut str {
+    buf.resize(buf.len() * 2, 0);
+    encode_in_place_slice(buf).expect("Len of the hex is always even")
+}
 
-#[inline(always)]
-fn encode_scalar(data: &[u8], result: &mut [MaybeUninit<u8>]) {
-    for (i, byte) in data.iter().enumerate() {
-        let hi = (byte >> 4) as usize;
-        let lo = (byte & 0xf) as usize;
-        result[i * 2].write(HEX_ENCODE_LUT[hi]);
-        result[i * 2 + 1].write(HEX_ENCODE_LUT[lo]);
-    }
+/// Like [`encode_in_place`], but takes the `Vec` and returns the hex as a
+/// `String` reusing its allocation.
+///
+/// ```
+/// let mut bytes = Vec::with_capacity(8);
+/// bytes.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+/// assert_eq!(muhex::encode_vec(bytes), "deadbeef");
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn encode_vec(mut buf: Vec<u8>) -> String {
+    encode_in_place(&mut buf);
+    // SAFETY: hex digits are ASCII
+    unsafe { String::from_utf8_unchecked(buf) }
 }
 
-// ─── Encode: public API ─────────────────────────────────────────────────
+// ─── Encode: separators and lines ───────────────────────────────────────
 
+/// Like [`encode`], but formats the output according to `options`, see
+/// [`EncodeOptions`].
+#[cfg(feature = "alloc")]
 #[inline]
-pub fn encode<T: AsRef<[u8]>>(v: T) -> String {
+pub fn encode_with<T: AsRef<[u8]>>(v: T, options: EncodeOptions) -> String {
     let data = v.as_ref();
-    let mut result = Vec::with_capacity(data.len() * 2);
-    encode_to_buf(data, result.spare_capacity_mut())
-        .expect("Len of result is always correct");
+    let len = options.encoded_len(data.len());
+    let mut result = Vec::with_capacity(len);
+    encode_options_into(data, &mut result.spare_capacity_mut()[..len], options);
     unsafe {
-        result.set_len(data.len() * 2);
+        result.set_len(len);
     }
     unsafe { String::from_utf8_unchecked(result) }
 }
 
+/// Like [`encode_to_buf`], but formats the output according to `options`.
+/// `dst` must be exactly [`EncodeOptions::encoded_len`] bytes long.
 #[inline]
-pub fn encode_to_buf<T, Dst>(v: T, dst: &mut Dst) -> Result<(), Error>
+pub fn encode_with_to_buf<T, Dst>(
+    v: T,
+    dst: &mut Dst,
+    options: EncodeOptions,
+) -> Result<(), Error>
 where
     T: AsRef<[u8]>,
     Dst: Buf + ?Sized,
 {
     let data = v.as_ref();
-    let expected_len = data.len() * 2;
-    // SAFETY: We only write fully initialized bytes through encode_simd_* and encode_scalar
+    let expected_len = options.encoded_len(data.len());
+    // SAFETY: We only write fully initialized bytes through the backend
     let dst = unsafe { dst.dst() };
     if dst.len() != expected_len {
-        return Err(Error::new(
-            ErrorKind::InvalidInput,
-            format!(
-                "output slice has wrong length: expected {}, got {}",
-                expected_len,
-                dst.len()
-            ),
-        ));
+        return Err(Error::InvalidOutputLength {
+            expected: expected_len,
+            actual: dst.len(),
+        });
     }
 
-    let mut pos = 0;
+    encode_options_into(data, dst, options);
 
-    #[cfg(target_arch = "x86_64")]
-    unsafe {
-        let input = data.as_ptr();
-        let output = dst.as_mut_ptr();
-
-        while pos + 64 <= data.len() {
-            encode_simd_64(input.add(pos), output.add(pos * 2));
-            pos += 64;
-        }
+    Ok(())
+}
 
-        while pos + 32 <= data.len() {
-            encode_simd_32(input.add(pos), output.add(pos * 2));
-            pos += 32;
+#[inline(always)]
+fn encode_options_into(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    options: EncodeOptions,
+) {
+    let lut = match options.upper {
+        false => &HEX_ENCODE_LUT,
+        true => &HEX_ENCODE_LUT_UPPER,
+    };
+    let width = options.line_width.unwrap_or(data.len().max(1));
+    let mut out = 0;
+
+    for (i, line) in data.chunks(width).enumerate() {
+        if i > 0 {
+            dst[out].write(b'\n');
+            out += 1;
         }
 
-        if pos < data.len() {
-            if data.len() >= 32 {
-                let start = data.len() - 32;
-                encode_simd_32(input.add(start), output.add(start * 2));
-            } else if data.len() >= 16 {
-                encode_simd_16(input, output);
-                if data.len() > 16 {
-                    let start = data.len() - 16;
-                    encode_simd_16(input.add(start), output.add(start * 2));
-                }
-            } else {
-                encode_scalar(&data[pos..], &mut dst[pos * 2..]);
-            }
+        let len = options.line_len(line.len());
+        let dst = &mut dst[out..out + len];
+        match options.separator {
+            Some(separator) => backend::encode_grouped(
+                line,
+                dst,
+                lut,
+                options.group_size,
+                separator,
+            ),
+            None => backend::encode(line, dst, lut),
         }
+        out += len;
     }
+}
 
-    #[cfg(not(target_arch = "x86_64"))]
-    {
-        while pos + 32 <= data.len() {
-            encode_simd_32(
-                &data[pos..pos + 32],
-                &mut dst[pos * 2..(pos + 32) * 2],
-            );
-            pos += 32;
+/// Encodes `data` one group at a time with `encode`, writing `separator`
+/// between groups. Used by the backends for groups and tails their shuffle
+/// kernels do not cover.
+#[inline(always)]
+fn encode_grouped_with(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+    group: usize,
+    separator: u8,
+    mut encode: impl FnMut(&[u8], &mut [MaybeUninit<u8>], &[u8; 16]),
+) {
+    let mut out = 0;
+
+    for (i, chunk) in data.chunks(group).enumerate() {
+        if i > 0 {
+            dst[out].write(separator);
+            out += 1;
         }
 
-        if pos < data.len() {
-            if data.len() >= 32 {
-                let start = data.len() - 32;
-                encode_simd_32(&data[start..], &mut dst[start * 2..]);
-            } else if data.len() >= 16 {
-                encode_simd_16(&data[0..16], &mut dst[0..32]);
-                if data.len() > 16 {
-                    let start = data.len() - 16;
-                    encode_simd_16(&data[start..], &mut dst[start * 2..]);
-                }
-            } else {
-                encode_scalar(&data[pos..], &mut dst[pos * 2..]);
-            }
-        }
+        encode(chunk, &mut dst[out..out + chunk.len() * 2], lut);
+        out += chunk.len() * 2;
     }
+}
 
-    Ok(())
+/// Straightforward version of [`encode_grouped_with`] to test the backends
+/// against.
+#[cfg(test)]
+fn encode_grouped_reference(
+    data: &[u8],
+    lut: &[u8; 16],
+    group: usize,
+    separator: u8,
+) -> Vec<u8> {
+    let groups: Vec<Vec<u8>> = data
+        .chunks(group)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .flat_map(|&b| {
+                    [lut[(b >> 4) as usize], lut[(b & 0xf) as usize]]
+                })
+                .collect()
+        })
+        .collect();
+    groups.join(&separator)
 }
 
-// ─── Decode: x86_64 vpermi2b-based fast paths ──────────────────────────
-
-// 128-byte LUT for vpermi2b hex decode.
-// vpermi2b uses 7-bit indices: bits[5:0] = offset within 64-byte source,
-// bit[6] selects source (0 = LUT_LO, 1 = LUT_HI).
-//
-// '0'-'9' (0x30-0x39): bit6=0, indices 0x30-0x39 in LUT_LO → values 0-9
-// 'A'-'F' (0x41-0x46): bit6=1, indices 0x01-0x06 in LUT_HI → values 10-15
-// 'a'-'f' (0x61-0x66): bit6=1, indices 0x21-0x26 in LUT_HI → values 10-15
-// Everything else → 0x80 (invalid sentinel, bit 7 set)
-#[cfg(target_arch = "x86_64")]
-#[repr(align(64))]
-struct Aligned64([u8; 64]);
-
-#[cfg(target_arch = "x86_64")]
-static HEX_DECODE_VPERMI2B_LO: Aligned64 = Aligned64([
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x00..0x07
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x08..0x0F
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x10..0x17
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x18..0x1F
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x20..0x27
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x28..0x2F
-    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, // 0x30..0x37 '0'..'7'
-    0x08, 0x09, 0x80, 0x80, 0x80, 0x80, 0x80,
-    0x80, // 0x38..0x3F '8','9',invalid
-]);
-
-#[cfg(target_arch = "x86_64")]
-static HEX_DECODE_VPERMI2B_HI: Aligned64 = Aligned64([
-    0x80, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
-    0x80, // 0x00..0x07 'A'..'F' at 01..06
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x08..0x0F
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x10..0x17
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x18..0x1F
-    0x80, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x0F,
-    0x80, // 0x20..0x27 'a'..'f' at 21..26
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x28..0x2F
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x30..0x37
-    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x38..0x3F
-]);
-
-/// Decode 128 hex chars → 64 output bytes using vpermi2b + vpmaddubsw.
-/// No deinterleave needed — processes interleaved hex pairs in-place.
-#[cfg(target_arch = "x86_64")]
-#[inline(always)]
-unsafe fn decode_x86_128(
-    input: *const u8,
-    output: *mut MaybeUninit<u8>,
-) -> bool {
-    unsafe {
-        let lut_lo =
-            _mm512_load_si512(HEX_DECODE_VPERMI2B_LO.0.as_ptr().cast());
-        let lut_hi =
-            _mm512_load_si512(HEX_DECODE_VPERMI2B_HI.0.as_ptr().cast());
-        let sentinel = _mm512_set1_epi8(0x80u8 as i8);
-        let merge = _mm512_set1_epi16(0x0110);
-
-        let v0 = _mm512_loadu_si512(input.cast());
-        let v1 = _mm512_loadu_si512(input.add(64).cast());
-
-        let mut nib0 = v0;
-        nib0 = _mm512_permutex2var_epi8(lut_lo, nib0, lut_hi);
-        let mut nib1 = v1;
-        nib1 = _mm512_permutex2var_epi8(lut_lo, nib1, lut_hi);
-
-        let err0 = _mm512_test_epi8_mask(nib0, sentinel);
-        let err1 = _mm512_test_epi8_mask(nib1, sentinel);
-        if (err0 | err1) != 0 {
-            return false;
-        }
+/// Byte shuffles that spread the 32 hex digits of 16 encoded bytes over 48
+/// output bytes, leaving a gap after every group for the separator.
+///
+/// Output vector `i` is `shuffle(hex[..16], lo[i]) | shuffle(hex[16..],
+/// hi[i]) | (sep[i] & separator)`, where indices with the top bit set select
+/// zero, as with `pshufb`. Only the first `len` output bytes are meaningful;
+/// they end with the separator after the last group.
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    feature = "portable-simd"
+))]
+struct GroupShuffle {
+    lo: [[u8; 16]; 3],
+    hi: [[u8; 16]; 3],
+    sep: [[u8; 16]; 3],
+    len: usize,
+}
 
-        let words0 = _mm512_maddubs_epi16(nib0, merge);
-        let words1 = _mm512_maddubs_epi16(nib1, merge);
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    feature = "portable-simd"
+))]
+impl GroupShuffle {
+    const fn new(group: usize) -> Self {
+        let mut shuffle = GroupShuffle {
+            lo: [[0x80; 16]; 3],
+            hi: [[0x80; 16]; 3],
+            sep: [[0; 16]; 3],
+            len: 32 + 16 / group,
+        };
 
-        let bytes0 = _mm512_cvtepi16_epi8(words0);
-        let bytes1 = _mm512_cvtepi16_epi8(words1);
+        let stride = group * 2 + 1;
+        let mut i = 0;
+        while i < shuffle.len {
+            let (vector, lane) = (i / 16, i % 16);
+            let digit = i / stride * group * 2 + i % stride;
+            if i % stride == group * 2 {
+                shuffle.sep[vector][lane] = 0xFF;
+            } else if digit < 16 {
+                shuffle.lo[vector][lane] = digit as u8;
+            } else {
+                shuffle.hi[vector][lane] = (digit - 16) as u8;
+            }
+            i += 1;
+        }
 
-        _mm256_storeu_si256(output.cast(), bytes0);
-        _mm256_storeu_si256(output.add(32).cast(), bytes1);
-        true
+        shuffle
     }
-}
 
-/// Decode 64 hex chars → 32 output bytes using vpermi2b + vpmaddubsw.
-#[cfg(target_arch = "x86_64")]
-#[inline(always)]
-unsafe fn decode_x86_64(
-    input: *const u8,
-    output: *mut MaybeUninit<u8>,
-) -> bool {
-    unsafe {
-        let lut_lo =
-            _mm512_load_si512(HEX_DECODE_VPERMI2B_LO.0.as_ptr().cast());
-        let lut_hi =
-            _mm512_load_si512(HEX_DECODE_VPERMI2B_HI.0.as_ptr().cast());
-        let sentinel = _mm512_set1_epi8(0x80u8 as i8);
-        let merge = _mm512_set1_epi16(0x0110);
-
-        let v = _mm512_loadu_si512(input.cast());
-        let mut nibbles = v;
-        nibbles = _mm512_permutex2var_epi8(lut_lo, nibbles, lut_hi);
-
-        let err = _mm512_test_epi8_mask(nibbles, sentinel);
-        if err != 0 {
-            return false;
+    /// Returns the shuffles for `group`, if it divides 16.
+    #[inline(always)]
+    fn for_group(group: usize) -> Option<&'static GroupShuffle> {
+        match group {
+            1 => Some(&GROUP_SHUFFLES[0]),
+            2 => Some(&GROUP_SHUFFLES[1]),
+            4 => Some(&GROUP_SHUFFLES[2]),
+            8 => Some(&GROUP_SHUFFLES[3]),
+            16 => Some(&GROUP_SHUFFLES[4]),
+            _ => None,
         }
-
-        let words = _mm512_maddubs_epi16(nibbles, merge);
-        let packed = _mm512_cvtepi16_epi8(words);
-
-        _mm256_storeu_si256(output.cast(), packed);
-        true
     }
 }
 
-// ─── Decode: AVX2/SSE remainder paths using pshufb ─────────────────────
-
-// For AVX2/SSE (no vpermi2b), use high-nibble classification of the
-// original input with offset + range validation via two pshufb LUTs.
-//
-// High nibble of input classifies char type:
-//   3 → '0'-'9' (and ':'-'?', need range check)
-//   4 → 'A'-'F' (and '@','G'-'O', need range check)
-//   6 → 'a'-'f' (and '`','g'-'o', need range check)
-//
-// offset_lut: maps high_nibble → offset to add to get nibble value
-// bound_lut: maps high_nibble → (9 - max_valid_low_nibble) as unsigned,
-//            so that adding it to the low nibble produces > 9 for invalid
-
-/// Decode 32 hex chars → 16 output bytes using AVX2 pshufb.
-#[cfg(target_arch = "x86_64")]
-#[inline(always)]
-unsafe fn decode_x86_32(
-    input: *const u8,
-    output: *mut MaybeUninit<u8>,
-) -> bool {
-    unsafe {
-        let v = _mm256_loadu_si256(input.cast());
-        let mask_0f = _mm256_set1_epi8(0x0F);
-
-        let hi_nib = _mm256_and_si256(_mm256_srli_epi16(v, 4), mask_0f);
-        let lo_nib = _mm256_and_si256(v, mask_0f);
-
-        let offset_lut = _mm256_broadcastsi128_si256(_mm_setr_epi8(
-            0, 0, 0, -0x30i8, -0x37i8, 0, -0x57i8, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ));
-
-        // Max low nibble LUT: invalid classes get 0, with min=0xFF,
-        // so no lo_nib can satisfy min <= lo_nib <= max.
-        let maxlo_lut = _mm256_broadcastsi128_si256(_mm_setr_epi8(
-            0, 0, 0, 9, // hi=0-3: invalid×3, '0'-'9' max lo=9
-            6, 0, 6,
-            0, // hi=4-7: 'A'-'F' max lo=6, invalid, 'a'-'f' max lo=6, invalid
-            0, 0, 0, 0, // hi=8-B: invalid
-            0, 0, 0, 0, // hi=C-F: invalid
-        ));
-
-        let minlo_lut = _mm256_broadcastsi128_si256(_mm_setr_epi8(
-            -1, -1, -1,
-            0, // hi=0-3: invalid(min=0xFF)×3, '0'-'9' min lo=0
-            1, -1, 1,
-            -1, // hi=4-7: 'A'-'F' min lo=1, invalid(0xFF), 'a'-'f' min lo=1, invalid(0xFF)
-            -1, -1, -1, -1, // hi=8-B: invalid
-            -1, -1, -1, -1, // hi=C-F: invalid
-        ));
-
-        let offsets = _mm256_shuffle_epi8(offset_lut, hi_nib);
-        let nibbles = _mm256_add_epi8(v, offsets);
-
-        let max_vals = _mm256_shuffle_epi8(maxlo_lut, hi_nib);
-        let min_vals = _mm256_shuffle_epi8(minlo_lut, hi_nib);
-
-        let clamped_hi = _mm256_min_epu8(lo_nib, max_vals);
-        let clamped_lo = _mm256_max_epu8(lo_nib, min_vals);
-        let ok_hi = _mm256_cmpeq_epi8(clamped_hi, lo_nib);
-        let ok_lo = _mm256_cmpeq_epi8(clamped_lo, lo_nib);
-        let ok = _mm256_and_si256(ok_hi, ok_lo);
-        if _mm256_movemask_epi8(ok) != -1i32 {
-            return false;
-        }
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    feature = "portable-simd"
+))]
+static GROUP_SHUFFLES: [GroupShuffle; 5] = [
+    GroupShuffle::new(1),
+    GroupShuffle::new(2),
+    GroupShuffle::new(4),
+    GroupShuffle::new(8),
+    GroupShuffle::new(16),
+];
 
-        let merge = _mm256_set1_epi16(0x0110);
-        let words = _mm256_maddubs_epi16(nibbles, merge);
+// ─── Decode ─────────────────────────────────────────────────────────────
 
-        let packed = _mm256_cvtepi16_epi8(words);
-        _mm_storeu_si128(output.cast(), packed);
-        true
-    }
+/// Decodes `input` into `output`, which must be exactly half as long.
+///
+/// The input can be any byte slice, so hex read from a socket or file can
+/// be decoded without validating it as UTF-8 first.
+#[inline(always)]
+pub fn decode_to_buf<T, Dst>(input: T, output: &mut Dst) -> Result<(), Error>
+where
+    T: AsRef<[u8]>,
+    Dst: Buf + ?Sized,
+{
+    decode_bytes_to_buf(input.as_ref(), output, DecodeOptions::default())
 }
 
-/// Decode 16 hex chars → 8 output bytes using SSE pshufb.
-#[cfg(target_arch = "x86_64")]
+/// Like [`decode_to_buf`], but validates the input according to `options`.
 #[inline(always)]
-unsafe fn decode_x86_16(
-    input: *const u8,
-    output: *mut MaybeUninit<u8>,
-) -> bool {
-    unsafe {
-        let v = _mm_loadu_si128(input.cast());
-        let mask_0f = _mm_set1_epi8(0x0F);
-
-        let hi_nib = _mm_and_si128(_mm_srli_epi16(v, 4), mask_0f);
-        let lo_nib = _mm_and_si128(v, mask_0f);
-
-        let offset_lut = _mm_setr_epi8(
-            0, 0, 0, -0x30i8, -0x37i8, 0, -0x57i8, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        );
-
-        let maxlo_lut =
-            _mm_setr_epi8(0, 0, 0, 9, 6, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0);
-
-        let minlo_lut = _mm_setr_epi8(
-            -1, -1, -1, 0, 1, -1, 1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-        );
-
-        let offsets = _mm_shuffle_epi8(offset_lut, hi_nib);
-        let nibbles = _mm_add_epi8(v, offsets);
-
-        let max_vals = _mm_shuffle_epi8(maxlo_lut, hi_nib);
-        let min_vals = _mm_shuffle_epi8(minlo_lut, hi_nib);
-
-        let clamped_hi = _mm_min_epu8(lo_nib, max_vals);
-        let clamped_lo = _mm_max_epu8(lo_nib, min_vals);
-        let ok_hi = _mm_cmpeq_epi8(clamped_hi, lo_nib);
-        let ok_lo = _mm_cmpeq_epi8(clamped_lo, lo_nib);
-        let ok = _mm_and_si128(ok_hi, ok_lo);
-        if _mm_movemask_epi8(ok) != 0xFFFF {
-            return false;
-        }
-
-        let merge = _mm_set1_epi16(0x0110);
-        let words = _mm_maddubs_epi16(nibbles, merge);
-        let packed = _mm_packus_epi16(words, _mm_setzero_si128());
-        _mm_storel_epi64(output.cast(), packed);
-        true
-    }
+pub fn decode_to_buf_with<T, Dst>(
+    input: T,
+    output: &mut Dst,
+    options: DecodeOptions,
+) -> Result<(), Error>
+where
+    T: AsRef<[u8]>,
+    Dst: Buf + ?Sized,
+{
+    decode_bytes_to_buf(input.as_ref(), output, options)
 }
 
-// ─── Decode ─────────────────────────────────────────────────────────────
-
 #[inline(always)]
-pub fn decode_to_buf<Dst>(input: &str, output: &mut Dst) -> Result<(), Error>
+fn decode_bytes_to_buf<Dst>(
+    input: &[u8],
+    output: &mut Dst,
+    options: DecodeOptions,
+) -> Result<(), Error>
 where
     Dst: Buf + ?Sized,
 {
-    let input = input.as_bytes();
     let input_len = input.len();
+    let pad = options.pad_odd && input_len & 1 != 0;
 
     // SAFETY: We only write fully initialized bytes through decode_into
     let output = unsafe { output.dst() };
-    if input_len != (output.len() << 1) {
-        if input_len & 1 != 0 {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "hex string length must be even",
-            ));
+    if input_len + pad as usize != (output.len() << 1) {
+        if input_len & 1 != 0 && !pad {
+            return Err(Error::OddLength);
         }
 
-        let expected_len = input_len >> 1;
-        return Err(Error::new(
-            ErrorKind::InvalidInput,
-            format!(
-                "output slice has wrong length: expected {}, got {}",
-                expected_len,
-                output.len()
-            ),
-        ));
+        return Err(Error::InvalidOutputLength {
+            expected: (input_len + 1) >> 1,
+            actual: output.len(),
+        });
     }
 
-    decode_into(input, output)
+    match pad {
+        true => decode_padded_into(input, output, options.case),
+        false => decode_into(input, output, options.case),
+    }
 }
 
 #[inline]
-pub fn decode_to_slice(input: &str, output: &mut [u8]) -> Result<(), Error> {
+pub fn decode_to_slice<T: AsRef<[u8]>>(
+    input: T,
+    output: &mut [u8],
+) -> Result<(), Error> {
     decode_to_buf(input, output)
 }
 
-#[inline(always)]
-fn invalid_hex_char_error() -> Error {
-    Error::from(ErrorKind::InvalidData)
-}
-
+/// Decodes `input` into a fixed-size array, without touching the heap.
+///
+/// Fails with [`Error::InvalidOutputLength`] unless `input` holds exactly
+/// `N` bytes.
+///
+/// ```
+/// let hash: [u8; 4] = muhex::decode_array("deadbeef")?;
+/// assert_eq!(hash, [0xde, 0xad, 0xbe, 0xef]);
+/// assert!(muhex::decode_array::<4>("dead").is_err());
+/// # Ok::<(), muhex::Error>(())
+/// ```
 #[inline]
-pub fn decode(input: &str) -> Result<Vec<u8>, Error> {
-    let input = input.as_bytes();
-    let n = input.len();
+pub fn decode_array<const N: usize>(
+    input: impl AsRef<[u8]>,
+) -> Result<[u8; N], Error> {
+    let mut output = [MaybeUninit::<u8>::uninit(); N];
+    let options = DecodeOptions::default();
+    decode_bytes_to_buf(input.as_ref(), &mut output[..], options)?;
+    // SAFETY: decoding succeeded, so every byte of `output` was written
+    Ok(unsafe { output.as_ptr().cast::<[u8; N]>().read() })
+}
 
-    if n % 2 != 0 {
-        return Err(Error::new(
-            ErrorKind::InvalidInput,
-            "input length must be even",
-        ));
+/// Builds the error for a block starting at `from` that failed to decode.
+///
+/// Blocks are decoded front to back and only the failure is reported, so
+/// everything before `from` is known to be valid and the first invalid
+/// byte from there on is the first one in the whole input.
+#[cold]
+#[inline(never)]
+fn invalid_hex_char_error(input: &[u8], from: usize, case: Case) -> Error {
+    let lut = case.decode_lut();
+    let index = input[from..]
+        .iter()
+        .position(|&byte| lut[byte as usize] == 255)
+        .map_or(from, |offset| from + offset);
+
+    Error::InvalidChar {
+        byte: input[index],
+        index,
     }
+}
 
-    let mut output = Vec::with_capacity(n / 2);
-    decode_into(input, output.spare_capacity_mut())?;
-    unsafe { output.set_len(n / 2) };
-    Ok(output)
+/// Decodes `input` into a new `Vec`.
+///
+/// The input can be any byte slice, so hex read from a socket or file can
+/// be decoded without validating it as UTF-8 first.
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn decode<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, Error> {
+    decode_bytes(input.as_ref(), DecodeOptions::default())
 }
 
-const HEX_DECODE_LUT: [u8; 256] = {
-    let mut lut = [255u8; 256]; // 255 = invalid
-    let mut i = 0;
-    while i < 256 {
-        lut[i] = match i as u8 {
-            b'0'..=b'9' => i as u8 - b'0',
-            b'A'..=b'F' => i as u8 - b'A' + 10,
-            b'a'..=b'f' => i as u8 - b'a' + 10,
-            _ => 255,
-        };
-        i += 1;
-    }
-    lut
-};
+/// Like [`decode`], but validates the input according to `options`.
+///
+/// ```
+/// use muhex::{Case, DecodeOptions};
+///
+/// let lower = DecodeOptions {
+///     case: Case::Lower,
+///     ..DecodeOptions::default()
+/// };
+/// assert_eq!(muhex::decode_with("c0ffee", lower)?, [0xc0, 0xff, 0xee]);
+/// assert!(muhex::decode_with("C0FFEE", lower).is_err());
+///
+/// let padded = DecodeOptions {
+///     pad_odd: true,
+///     ..DecodeOptions::default()
+/// };
+/// assert_eq!(muhex::decode_with("fff", padded)?, [0x0f, 0xff]);
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn decode_with<T: AsRef<[u8]>>(
+    input: T,
+    options: DecodeOptions,
+) -> Result<Vec<u8>, Error> {
+    decode_bytes(input.as_ref(), options)
+}
 
-// x86_64: use intrinsic-based decode paths
-#[cfg(target_arch = "x86_64")]
+#[cfg(feature = "alloc")]
 #[inline(always)]
-fn decode_into(
+fn decode_bytes(
     input: &[u8],
-    output: &mut [MaybeUninit<u8>],
-) -> Result<(), Error> {
+    options: DecodeOptions,
+) -> Result<Vec<u8>, Error> {
     let n = input.len();
 
-    if n % 2 != 0 {
-        return Err(Error::new(
-            ErrorKind::InvalidInput,
-            "input length must be even",
-        ));
+    if n % 2 != 0 && !options.pad_odd {
+        return Err(Error::OddLength);
     }
 
-    let mut pos = 0;
-    let mut out_pos = 0;
+    let len = n.div_ceil(2);
+    let mut output = Vec::with_capacity(len);
+    let dst = &mut output.spare_capacity_mut()[..len];
+    match n % 2 != 0 {
+        true => decode_padded_into(input, dst, options.case)?,
+        false => decode_into(input, dst, options.case)?,
+    }
+    unsafe { output.set_len(len) };
+    Ok(output)
+}
 
-    unsafe {
-        // Main loop: 128 hex chars → 64 output bytes per iteration
-        while pos + 128 <= n {
-            if !decode_x86_128(
-                input.as_ptr().add(pos),
-                output.as_mut_ptr().add(out_pos),
-            ) {
-                return Err(invalid_hex_char_error());
-            }
-            pos += 128;
-            out_pos += 64;
-        }
+/// Decodes odd-length `input` as if it had a leading `0` digit, into an
+/// `output` of `(input.len() + 1) / 2` bytes.
+#[inline]
+fn decode_padded_into(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    let (&first, rest) = input.split_first().ok_or(Error::OddLength)?;
+    let nibble = case.decode_lut()[first as usize];
+    if nibble == 255 {
+        return Err(Error::InvalidChar {
+            byte: first,
+            index: 0,
+        });
+    }
+    output[0].write(nibble);
+
+    decode_into(rest, &mut output[1..], case).map_err(|err| match err {
+        Error::InvalidChar { byte, index } => Error::InvalidChar {
+            byte,
+            index: index + 1,
+        },
+        err => err,
+    })
+}
 
-        // Remainder: 64 hex chars
-        if pos + 64 <= n {
-            if !decode_x86_64(
-                input.as_ptr().add(pos),
-                output.as_mut_ptr().add(out_pos),
-            ) {
-                return Err(invalid_hex_char_error());
-            }
-            pos += 64;
-            out_pos += 32;
-        }
+/// Decodes `input` into one 4-bit value per hex digit, for nibble paths
+/// such as the keys of Merkle Patricia tries and packed BCD.
+///
+/// Unlike [`decode`], the input can have any length.
+///
+/// ```
+/// assert_eq!(muhex::decode_nibbles("0fa")?, [0x0, 0xf, 0xa]);
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn decode_nibbles<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, Error> {
+    const CHUNK_LEN: usize = 256;
+
+    let input = input.as_ref();
+    let (pairs, last) = input.split_at(input.len() & !1);
+    let mut nibbles = Vec::with_capacity(input.len());
+    let mut bytes = [0u8; CHUNK_LEN];
+
+    for (i, chunk) in pairs.chunks(CHUNK_LEN * 2).enumerate() {
+        let bytes = &mut bytes[..chunk.len() / 2];
+        // SAFETY: `decode_into` only writes initialized bytes
+        decode_into(chunk, unsafe { bytes.dst() }, Case::Any).map_err(
+            |err| match err {
+                Error::InvalidChar { byte, index } => Error::InvalidChar {
+                    byte,
+                    index: i * CHUNK_LEN * 2 + index,
+                },
+                err => err,
+            },
+        )?;
+        nibbles.extend(bytes.iter().flat_map(|&b| [b >> 4, b & 0x0f]));
     }
 
-    // Handle remainder with overlapping SIMD reads
-    if pos < n {
-        if n >= 128 {
-            // Re-decode last 128 hex bytes via overlapping
-            let start = n - 128;
-            let out_start = start / 2;
-            if !unsafe {
-                decode_x86_128(
-                    input.as_ptr().add(start),
-                    output.as_mut_ptr().add(out_start),
-                )
-            } {
-                return Err(invalid_hex_char_error());
-            }
-        } else if n >= 64 {
-            // Re-decode last 64 hex bytes via overlapping
-            let start = n - 64;
-            let out_start = start / 2;
-            if !unsafe {
-                decode_x86_64(
-                    input.as_ptr().add(start),
-                    output.as_mut_ptr().add(out_start),
-                )
-            } {
-                return Err(invalid_hex_char_error());
-            }
-        } else if n >= 32 {
-            // Use AVX2 32-byte path with overlapping
-            unsafe {
-                // First 32 hex bytes
-                if pos + 32 <= n {
-                    if !decode_x86_32(
-                        input.as_ptr().add(pos),
-                        output.as_mut_ptr().add(out_pos),
-                    ) {
-                        return Err(invalid_hex_char_error());
-                    }
-                    pos += 32;
-                }
-                // Overlapping last 32 hex bytes if remainder
-                if pos < n {
-                    let start = n - 32;
-                    let out_start = start / 2;
-                    if !decode_x86_32(
-                        input.as_ptr().add(start),
-                        output.as_mut_ptr().add(out_start),
-                    ) {
-                        return Err(invalid_hex_char_error());
-                    }
-                }
-            }
-        } else if n >= 16 {
-            // Use SSE 16-byte path with overlapping
-            unsafe {
-                if !decode_x86_16(
-                    input.as_ptr().add(pos),
-                    output.as_mut_ptr().add(out_pos),
-                ) {
-                    return Err(invalid_hex_char_error());
-                }
-                if n > 16 {
-                    let start = n - 16;
-                    let out_start = start / 2;
-                    if !decode_x86_16(
-                        input.as_ptr().add(start),
-                        output.as_mut_ptr().add(out_start),
-                    ) {
-                        return Err(invalid_hex_char_error());
-                    }
-                }
-            }
-        } else {
-            // < 16 hex bytes: scalar LUT fallback
-            let remaining = n - pos;
-            decode_remainder_lut(input, output, pos, out_pos, remaining)?;
+    if let [byte] = *last {
+        let nibble = HEX_DECODE_LUT[byte as usize];
+        if nibble == 255 {
+            let index = pairs.len();
+            return Err(Error::InvalidChar { byte, index });
         }
+        nibbles.push(nibble);
     }
 
-    Ok(())
+    Ok(nibbles)
 }
 
-// Non-x86_64: portable_simd fallback decode
-#[cfg(not(target_arch = "x86_64"))]
-#[inline(always)]
-fn decode_simd_64(
-    input: &[u8],
-    output: &mut [MaybeUninit<u8>],
-) -> Result<(), Error> {
-    let chunk_vec: SimdU8<64> = Simd::from_slice(input);
-
-    let high_bytes: SimdU8<32> = simd_swizzle!(
-        chunk_vec,
-        [
-            0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30, 32, 34,
-            36, 38, 40, 42, 44, 46, 48, 50, 52, 54, 56, 58, 60, 62
-        ]
-    );
-    let low_bytes: SimdU8<32> = simd_swizzle!(
-        chunk_vec,
-        [
-            1, 3, 5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25, 27, 29, 31, 33, 35,
-            37, 39, 41, 43, 45, 47, 49, 51, 53, 55, 57, 59, 61, 63
-        ]
-    );
-
-    let (high_nibbles, high_valid) = decode_hex_nibbles(high_bytes);
-    let (low_nibbles, low_valid) = decode_hex_nibbles(low_bytes);
-
-    if !(high_valid & low_valid) {
-        return Err(invalid_hex_char_error());
+// ─── Decode: in place ───────────────────────────────────────────────────
+
+/// Decodes the hex in `buf` into its own front half, returning the decoded
+/// bytes, so large inputs can be decoded without a second buffer.
+///
+/// Each block of digits is decoded into a small stack buffer before it is
+/// copied back, so the kernels never see their output overlap their input.
+/// Block `i` is written to `buf[i * n..]`, always before block `i + 1` is
+/// read from `buf[(i + 1) * 2 * n..]`. If decoding fails, `buf` may have
+/// been partially overwritten.
+///
+/// ```
+/// let mut buf = *b"c0ffee";
+/// assert_eq!(muhex::decode_in_place(&mut buf)?, [0xc0, 0xff, 0xee]);
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[inline]
+pub fn decode_in_place(buf: &mut [u8]) -> Result<&mut [u8], Error> {
+    if buf.len() % 2 != 0 {
+        return Err(Error::OddLength);
     }
-    let decoded = (high_nibbles << SimdU8::<32>::splat(4)) | low_nibbles;
 
-    let decoded: &[u8; 32] = decoded.as_array();
-    let uninit_src: &[MaybeUninit<u8>; 32] =
-        unsafe { std::mem::transmute(decoded) };
-    output.copy_from_slice(uninit_src);
-    Ok(())
+    let len = buf.len() / 2;
+    let mut bytes = [0u8; IN_PLACE_BLOCK_LEN];
+
+    for start in (0..len).step_by(IN_PLACE_BLOCK_LEN) {
+        let end = len.min(start + IN_PLACE_BLOCK_LEN);
+        let bytes = &mut bytes[..end - start];
+        // SAFETY: `decode_into` only writes initialized bytes
+        decode_into(
+            &buf[start * 2..end * 2],
+            unsafe { bytes.dst() },
+            Case::Any,
+        )
+        .map_err(|err| match err {
+            Error::InvalidChar { byte, index } => Error::InvalidChar {
+                byte,
+                index: start * 2 + index,
+            },
+            err => err,
+        })?;
+        buf[start..end].copy_from_slice(bytes);
+    }
+
+    Ok(&mut buf[..len])
 }
 
-#[cfg(not(target_arch = "x86_64"))]
-#[inline(always)]
-fn decode_simd_32(
-    input: &[u8],
-    output: &mut [MaybeUninit<u8>],
-) -> Result<(), Error> {
-    let chunk_vec: SimdU8<32> = Simd::from_slice(input);
-    let high_bytes: SimdU8<16> = simd_swizzle!(
-        chunk_vec,
-        [0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30]
-    );
-    let low_bytes: SimdU8<16> = simd_swizzle!(
-        chunk_vec,
-        [1, 3, 5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25, 27, 29, 31]
-    );
-
-    let (high_nibbles, high_valid) = decode_hex_nibbles(high_bytes);
-    let (low_nibbles, low_valid) = decode_hex_nibbles(low_bytes);
-
-    if !(high_valid & low_valid) {
-        return Err(invalid_hex_char_error());
-    }
+/// Like [`decode_in_place`], but takes and returns a `Vec`, truncated to
+/// the decoded bytes.
+///
+/// ```
+/// let hex = b"deadbeef".to_vec();
+/// assert_eq!(muhex::decode_vec(hex)?, [0xde, 0xad, 0xbe, 0xef]);
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn decode_vec(mut buf: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let len = decode_in_place(&mut buf)?.len();
+    buf.truncate(len);
+    Ok(buf)
+}
 
-    let decoded = (high_nibbles << SimdU8::<16>::splat(4)) | low_nibbles;
-    let decoded: &[u8; 16] = decoded.as_array();
-    let uninit_src: &[MaybeUninit<u8>; 16] =
-        unsafe { std::mem::transmute(decoded) };
-    output.copy_from_slice(uninit_src);
-    Ok(())
+// ─── Decode: lenient ────────────────────────────────────────────────────
+
+/// Input bytes compacted per round of [`decode_lenient`].
+const LENIENT_BLOCK_LEN: usize = 512;
+
+/// Decodes `input` into `output` while skipping the bytes in `ignore`,
+/// returning the number of bytes written.
+///
+/// Ignored bytes may appear anywhere, even between the two digits of a
+/// byte. `output` may be longer than needed; if it is too short, this
+/// fails with [`Error::InvalidOutputLength`] holding the full decoded
+/// length. Invalid characters are reported at their index in `input`.
+///
+/// ```
+/// use muhex::IgnoreSet;
+///
+/// let mut fingerprint = [0u8; 32];
+/// let len = muhex::decode_lenient(
+///     "DE:AD:BE:EF\n01:23",
+///     &mut fingerprint[..],
+///     IgnoreSet::default(),
+/// )?;
+/// assert_eq!(fingerprint[..len], [0xde, 0xad, 0xbe, 0xef, 0x01, 0x23]);
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[inline]
+pub fn decode_lenient<T, Dst>(
+    input: T,
+    output: &mut Dst,
+    ignore: IgnoreSet,
+) -> Result<usize, Error>
+where
+    T: AsRef<[u8]>,
+    Dst: Buf + ?Sized,
+{
+    decode_lenient_with(input, output, ignore, Case::Any)
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+/// Like [`decode_lenient`], but only accepts digits of the given `case`.
+///
+/// ```
+/// use muhex::{Case, Error, IgnoreSet};
+///
+/// let (mut key, ignore) = ([0u8; 4], IgnoreSet::WHITESPACE);
+/// let decode = |hex, key: &mut [u8]| {
+///     muhex::decode_lenient_with(hex, key, ignore, Case::Upper)
+/// };
+/// let len = decode("DE AD", &mut key)?;
+/// assert_eq!(key[..len], [0xde, 0xad]);
+///
+/// let err = decode("DE ad", &mut key);
+/// assert_eq!(err, Err(Error::InvalidChar { byte: b'a', index: 3 }));
+/// # Ok::<(), muhex::Error>(())
+/// ```
 #[inline]
-fn decode_into(
+pub fn decode_lenient_with<T, Dst>(
+    input: T,
+    output: &mut Dst,
+    ignore: IgnoreSet,
+    case: Case,
+) -> Result<usize, Error>
+where
+    T: AsRef<[u8]>,
+    Dst: Buf + ?Sized,
+{
+    // SAFETY: We only write fully initialized bytes through the backend
+    let output = unsafe { output.dst() };
+    lenient_into(
+        input.as_ref(),
+        output,
+        &ignore,
+        case,
+        backend::compact,
+        backend::decode,
+    )
+}
+
+/// The body of [`decode_lenient_with`], taking the compaction and decoding
+/// kernels so that tests can run it on every backend.
+#[inline(always)]
+fn lenient_into(
     input: &[u8],
     output: &mut [MaybeUninit<u8>],
-) -> Result<(), Error> {
-    let n = input.len();
+    ignore: &IgnoreSet,
+    case: Case,
+    compact: impl Fn(&[u8], &mut [u8], &IgnoreSet) -> usize,
+    decode: impl Fn(&[u8], &mut [MaybeUninit<u8>], Case) -> Result<(), Error>,
+) -> Result<usize, Error> {
+    // Compacted digits, starting with the odd one left from the last block
+    let mut hex = [0u8; LENIENT_BLOCK_LEN + 1];
+    let mut pending = 0;
+    let mut written = 0;
+
+    for block in input.chunks(LENIENT_BLOCK_LEN) {
+        pending +=
+            compact(block, &mut hex[pending..pending + block.len()], ignore);
+
+        let len = pending / 2;
+        let Some(output) = output.get_mut(written..written + len) else {
+            return Err(lenient_error(input, output.len(), ignore, case));
+        };
+        decode(&hex[..len * 2], output, case)
+            .map_err(|_| lenient_error(input, output.len(), ignore, case))?;
+        written += len;
 
-    if n % 2 != 0 {
-        return Err(Error::new(
-            ErrorKind::InvalidInput,
-            "input length must be even",
-        ));
+        if pending % 2 != 0 {
+            hex[0] = hex[pending - 1];
+        }
+        pending %= 2;
     }
 
-    let mut pos = 0;
-    let mut out_pos = 0;
-
-    while pos + 64 <= n {
-        decode_simd_64(
-            &input[pos..pos + 64],
-            &mut output[out_pos..out_pos + 32],
-        )?;
-        pos += 64;
-        out_pos += 32;
+    // The odd digit left over has not been validated yet
+    match pending {
+        0 => Ok(written),
+        _ => Err(lenient_error(input, output.len(), ignore, case)),
     }
+}
 
-    if pos < n {
-        if n >= 64 {
-            let start = n - 64;
-            let out_start = start / 2;
-            decode_simd_64(
-                &input[start..start + 64],
-                &mut output[out_start..out_start + 32],
-            )?;
-        } else {
-            while pos + 32 <= n {
-                decode_simd_32(
-                    &input[pos..pos + 32],
-                    &mut output[out_pos..out_pos + 16],
-                )?;
-                pos += 32;
-                out_pos += 16;
-            }
-            if pos < n {
-                if n >= 32 {
-                    let start = n - 32;
-                    let out_start = start / 2;
-                    decode_simd_32(
-                        &input[start..start + 32],
-                        &mut output[out_start..out_start + 16],
-                    )?;
-                } else {
-                    let remaining = n - pos;
-                    decode_remainder_lut(
-                        input, output, pos, out_pos, remaining,
-                    )?;
-                }
-            }
+/// Finds out why lenient decoding of `input` into `output_len` bytes
+/// failed, rescanning it from the start so invalid characters are reported
+/// at their index in `input` rather than in the compacted digits.
+#[cold]
+#[inline(never)]
+fn lenient_error(
+    input: &[u8],
+    output_len: usize,
+    ignore: &IgnoreSet,
+    case: Case,
+) -> Error {
+    let lut = case.decode_lut();
+    let mut digits = 0;
+
+    for (index, &byte) in input.iter().enumerate() {
+        if ignore.contains(byte) {
+            continue;
+        }
+        if lut[byte as usize] == 255 {
+            return Error::InvalidChar { byte, index };
         }
+        digits += 1;
     }
 
-    Ok(())
+    match digits % 2 {
+        0 => Error::InvalidOutputLength {
+            expected: digits / 2,
+            actual: output_len,
+        },
+        _ => Error::OddLength,
+    }
 }
 
-#[cfg(not(target_arch = "x86_64"))]
+/// Copies the bytes of `input` not in `ignore` to the front of `output`,
+/// returning how many there were.
 #[inline(always)]
-fn decode_hex_nibbles<const LANES: usize>(
-    n: SimdU8<LANES>,
-) -> (SimdU8<LANES>, bool) {
-    let zero = SimdU8::<LANES>::splat(b'0');
-    let nine = SimdU8::<LANES>::splat(b'9');
-    let upper_a = SimdU8::<LANES>::splat(b'A');
-    let gap = SimdU8::<LANES>::splat(b'A' - b'9' - 1);
-    let lower_gap = SimdU8::<LANES>::splat(b'a' - b'A');
+fn compact_scalar(
+    input: &[u8],
+    output: &mut [u8],
+    ignore: &IgnoreSet,
+) -> usize {
+    let mut len = 0;
+
+    for &byte in input {
+        output[len] = byte;
+        len += !ignore.contains(byte) as usize;
+    }
 
-    let mut val = n - zero;
+    len
+}
+
+/// For every 8-bit mask of bytes to keep, the indices of those bytes in
+/// order, for compacting 8 bytes at a time with a byte shuffle.
+#[cfg(any(
+    all(target_arch = "x86_64", target_feature = "sse2"),
+    feature = "portable-simd"
+))]
+static COMPACT_SHUFFLES: [[u8; 8]; 256] = {
+    let mut shuffles = [[0x80; 8]; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let (mut bit, mut len) = (0, 0);
+        while bit < 8 {
+            if mask & (1 << bit) != 0 {
+                shuffles[mask][len] = bit as u8;
+                len += 1;
+            }
+            bit += 1;
+        }
+        mask += 1;
+    }
+    shuffles
+};
 
-    let gt_nine = n.simd_gt(nine);
-    val = gt_nine.select(val - gap, val);
+/// Builds the scalar decode LUT for the letter cases `case` accepts.
+const fn build_decode_lut(case: Case) -> [u8; 256] {
+    let mut lut = [255u8; 256]; // 255 = invalid
+    let mut i = 0;
+    while i < 256 {
+        lut[i] = match i as u8 {
+            b'0'..=b'9' => i as u8 - b'0',
+            b'A'..=b'F' if case.accepts_upper() => i as u8 - b'A' + 10,
+            b'a'..=b'f' if case.accepts_lower() => i as u8 - b'a' + 10,
+            _ => 255,
+        };
+        i += 1;
+    }
+    lut
+}
 
-    let ge_lower_a = n.simd_ge(SimdU8::<LANES>::splat(b'a'));
-    val = ge_lower_a.select(val - lower_gap, val);
+static HEX_DECODE_LUT: [u8; 256] = build_decode_lut(Case::Any);
+static HEX_DECODE_LUT_LOWER: [u8; 256] = build_decode_lut(Case::Lower);
+static HEX_DECODE_LUT_UPPER: [u8; 256] = build_decode_lut(Case::Upper);
+
+impl Case {
+    #[inline(always)]
+    fn decode_lut(self) -> &'static [u8; 256] {
+        match self {
+            Case::Lower => &HEX_DECODE_LUT_LOWER,
+            Case::Upper => &HEX_DECODE_LUT_UPPER,
+            Case::Any => &HEX_DECODE_LUT,
+        }
+    }
+}
 
-    let ge_upper_a = n.simd_ge(upper_a);
-    let in_gap = gt_nine & !ge_upper_a;
-    let valid = val.simd_le(SimdU8::<LANES>::splat(15)) & !in_gap;
+#[inline(always)]
+fn decode_into(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    if input.len() % 2 != 0 {
+        return Err(Error::OddLength);
+    }
 
-    (val, valid.all())
+    backend::decode(input, output, case)
 }
 
 #[inline(always)]
@@ -945,15 +1006,17 @@ fn decode_remainder_lut(
     mut pos: usize,
     mut out_pos: usize,
     remaining: usize,
+    case: Case,
 ) -> Result<(), Error> {
+    let lut = case.decode_lut();
     let end = pos + remaining;
 
     while pos < end {
-        let hi = HEX_DECODE_LUT[input[pos] as usize];
-        let lo = HEX_DECODE_LUT[input[pos + 1] as usize];
+        let hi = lut[input[pos] as usize];
+        let lo = lut[input[pos + 1] as usize];
 
         if (hi | lo) == 255 {
-            return Err(Error::from(ErrorKind::InvalidData));
+            return Err(invalid_hex_char_error(input, pos, case));
         }
 
         output[out_pos].write((hi << 4) | lo);
@@ -964,7 +1027,7 @@ fn decode_remainder_lut(
     Ok(())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use proptest::prelude::*;
 
@@ -973,6 +1036,185 @@ mod tests {
         prop_assert_eq!(super::encode(&input), hex::encode(input))
     }
 
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_hex_encode_upper_parity(input: Vec<u8>) {
+        prop_assert_eq!(super::encode_upper(&input), hex::encode_upper(input))
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_encode_upper_to_buf(input: Vec<u8>) {
+        let mut buffer = vec![0u8; input.len() * 2];
+        super::encode_upper_to_buf(&input, &mut buffer[..])?;
+        prop_assert_eq!(buffer, hex::encode_upper(&input).into_bytes());
+        prop_assert_eq!(
+            super::encode_upper_to_buf(&input, &mut [0u8; 1][..]).is_err(),
+            input.len() * 2 != 1
+        );
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_encode_with(
+        #[strategy(proptest::collection::vec(any::<u8>(), 0..300))] input: Vec<
+            u8,
+        >,
+        #[strategy(proptest::option::of(b' '..b'~'))] separator: Option<u8>,
+        #[strategy(1usize..20)] group: usize,
+        #[strategy(proptest::option::of(1usize..70))] line_width: Option<usize>,
+        upper: bool,
+    ) {
+        use super::{EncodeOptions, Error};
+
+        let mut options = EncodeOptions::new().group_size(group).upper(upper);
+        if let Some(separator) = separator {
+            options = options.separator(separator);
+        }
+        if let Some(line_width) = line_width {
+            options = options.line_width(line_width);
+        }
+
+        let lines: Vec<String> = input
+            .chunks(line_width.unwrap_or(input.len().max(1)))
+            .map(|line| {
+                let groups: Vec<String> = line
+                    .chunks(group)
+                    .map(|group| match upper {
+                        false => hex::encode(group),
+                        true => hex::encode_upper(group),
+                    })
+                    .collect();
+                groups.join(&separator.map_or(String::new(), |separator| {
+                    char::from(separator).to_string()
+                }))
+            })
+            .collect();
+        let expected = lines.join("\n");
+
+        prop_assert_eq!(options.encoded_len(input.len()), expected.len());
+        prop_assert_eq!(&super::encode_with(&input, options), &expected);
+
+        let mut buffer = vec![0u8; expected.len()];
+        super::encode_with_to_buf(&input, &mut buffer[..], options)?;
+        prop_assert_eq!(buffer, expected.as_bytes());
+        prop_assert_eq!(
+            super::encode_with_to_buf(&input, &mut [0u8; 1][..], options),
+            if expected.len() == 1 {
+                Ok(())
+            } else {
+                Err(Error::InvalidOutputLength {
+                    expected: expected.len(),
+                    actual: 1,
+                })
+            }
+        );
+    }
+
+    /// Hex digits mixed with ignored bytes and the odd invalid `x`.
+    fn lenient_chars() -> impl Strategy<Value = Vec<u8>> {
+        let chars = b"0123456789abcdefABCDEF :-_\r\nx".to_vec();
+        proptest::collection::vec(proptest::sample::select(chars), 0..1500)
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_decode_lenient(
+        #[strategy(lenient_chars())] input: Vec<u8>,
+        #[strategy(0usize..800)] output_len: usize,
+    ) {
+        use super::{Case, Error, IgnoreSet};
+
+        for case in [Case::Lower, Case::Upper, Case::Any] {
+            let invalid = |byte: u8| match case {
+                Case::Lower => byte == b'x' || byte.is_ascii_uppercase(),
+                Case::Upper => byte == b'x' || byte.is_ascii_lowercase(),
+                Case::Any => byte == b'x',
+            };
+            let digits: Vec<u8> = input
+                .iter()
+                .copied()
+                .filter(|byte| !IgnoreSet::default().contains(*byte))
+                .collect();
+            let expected = match input.iter().position(|&byte| invalid(byte)) {
+                Some(index) => Err(Error::InvalidChar {
+                    byte: input[index],
+                    index,
+                }),
+                None if digits.len() % 2 != 0 => Err(Error::OddLength),
+                None if digits.len() / 2 > output_len => {
+                    Err(Error::InvalidOutputLength {
+                        expected: digits.len() / 2,
+                        actual: output_len,
+                    })
+                }
+                None => Ok(hex::decode(&digits).unwrap()),
+            };
+
+            let mut output = vec![0u8; output_len];
+            let decoded = super::decode_lenient_with(
+                &input,
+                &mut output[..],
+                IgnoreSet::default(),
+                case,
+            )
+            .map(|len| output[..len].to_vec());
+            prop_assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn test_ignore_set() {
+        use super::IgnoreSet;
+
+        for byte in 0..=0x7F {
+            assert_eq!(
+                IgnoreSet::WHITESPACE.contains(byte),
+                byte.is_ascii_whitespace()
+            );
+            assert_eq!(
+                IgnoreSet::default().contains(byte),
+                byte.is_ascii_whitespace() || b":-_".contains(&byte)
+            );
+            assert!(!IgnoreSet::NONE.contains(byte));
+        }
+        assert!(!IgnoreSet::default().contains(0xA0));
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_encode_in_place(
+        #[strategy(proptest::collection::vec(any::<u8>(), 0..3000))] input: Vec<
+            u8,
+        >,
+        spare: bool,
+    ) {
+        let expected = hex::encode(&input);
+
+        let mut buf = input.clone();
+        buf.resize(input.len() * 2, 0);
+        prop_assert_eq!(super::encode_in_place_slice(&mut buf)?, &expected[..]);
+
+        let mut bytes = Vec::with_capacity(input.len() * (1 + spare as usize));
+        bytes.extend_from_slice(&input);
+        let ptr = bytes.as_ptr();
+        prop_assert_eq!(super::encode_in_place(&mut bytes), &expected[..]);
+        prop_assert_eq!(&bytes, expected.as_bytes());
+        if spare {
+            prop_assert_eq!(bytes.as_ptr(), ptr);
+        }
+
+        let mut bytes = Vec::with_capacity(input.len() * (1 + spare as usize));
+        bytes.extend_from_slice(&input);
+        let ptr = bytes.as_ptr();
+        let encoded = super::encode_vec(bytes);
+        prop_assert_eq!(&encoded, &expected);
+        if spare {
+            prop_assert_eq!(encoded.as_ptr(), ptr);
+        }
+
+        buf.push(0);
+        prop_assert_eq!(
+            super::encode_in_place_slice(&mut buf),
+            Err(super::Error::OddLength)
+        );
+    }
+
     #[test_strategy::proptest(cases = 10000)]
     fn test_hex_decode_parity(input: String) {
         prop_assert_eq!(
@@ -981,6 +1223,20 @@ mod tests {
         )
     }
 
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_hex_decode_bytes_parity(input: Vec<u8>) {
+        prop_assert_eq!(
+            super::decode(&input).map_err(|_| ()),
+            hex::decode(&input).map_err(|_| ())
+        );
+
+        let mut buffer = vec![0u8; input.len() / 2];
+        prop_assert_eq!(
+            super::decode_to_slice(&input, &mut buffer).is_ok(),
+            hex::decode_to_slice(&input, &mut buffer).is_ok()
+        );
+    }
+
     #[test_strategy::proptest(cases = 10000)]
     fn test_decode_to_slice_roundtrip(input: Vec<u8>) {
         let encoded = hex::encode(&input);
@@ -991,23 +1247,239 @@ mod tests {
 
     #[test_strategy::proptest(cases = 10000)]
     fn test_hex_roundtrip(input: Vec<u8>) {
-        prop_assert_eq!(super::decode(&super::encode(&input))?, input)
+        prop_assert_eq!(super::decode(super::encode(&input))?, input)
     }
 
     #[test_strategy::proptest(cases = 10000)]
     fn test_hex_roundtrip_parity(input: Vec<u8>) {
         prop_assert_eq!(
-            super::decode(&super::encode(&input)).is_ok(),
+            super::decode(super::encode(&input)).is_ok(),
             hex::decode(hex::encode(input)).is_ok()
         )
     }
 
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_decode_with_case(input: Vec<u8>) {
+        use super::{Case, DecodeOptions, Error};
+
+        let lower = super::encode(&input);
+        let upper = super::encode_upper(&input);
+        let first_letter = lower.bytes().position(|b| b.is_ascii_lowercase());
+
+        for (case, accepted, rejected) in
+            [(Case::Lower, &lower, &upper), (Case::Upper, &upper, &lower)]
+        {
+            let options = DecodeOptions {
+                case,
+                ..DecodeOptions::default()
+            };
+            prop_assert_eq!(&super::decode_with(accepted, options)?, &input);
+
+            let expected = match first_letter {
+                Some(index) => Err(Error::InvalidChar {
+                    byte: rejected.as_bytes()[index],
+                    index,
+                }),
+                None => Ok(input.clone()),
+            };
+            prop_assert_eq!(super::decode_with(rejected, options), expected);
+
+            let mut buffer = vec![0u8; input.len()];
+            super::decode_to_buf_with(accepted, &mut buffer[..], options)?;
+            prop_assert_eq!(&buffer, &input);
+        }
+
+        let any = DecodeOptions::default();
+        prop_assert_eq!(&super::decode_with(&upper, any)?, &input);
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_decode_array(input: [u8; 20], odd: bool) {
+        let encoded = super::encode(input);
+        prop_assert_eq!(super::decode_array::<20>(&encoded)?, input);
+
+        let short = &encoded[..38 + odd as usize];
+        prop_assert_eq!(
+            super::decode_array::<20>(short),
+            Err(match odd {
+                false => super::Error::InvalidOutputLength {
+                    expected: 19,
+                    actual: 20,
+                },
+                true => super::Error::OddLength,
+            })
+        );
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_decode_pad_odd(input: String) {
+        let options = super::DecodeOptions {
+            pad_odd: true,
+            ..super::DecodeOptions::default()
+        };
+        let padded = match input.len() % 2 {
+            0 => input.clone(),
+            _ => format!("0{input}"),
+        };
+        let offset = padded.len() - input.len();
+        let expected = super::decode(&padded).map_err(|err| match err {
+            super::Error::InvalidChar { byte, index } => {
+                super::Error::InvalidChar {
+                    byte,
+                    index: index - offset,
+                }
+            }
+            err => err,
+        });
+        prop_assert_eq!(super::decode_with(&input, options), expected.clone());
+
+        let mut buffer = vec![0u8; padded.len() / 2];
+        let result =
+            super::decode_to_buf_with(&input, &mut buffer[..], options);
+        prop_assert_eq!(result.map(|()| buffer), expected);
+    }
+
+    #[test]
+    fn test_decode_pad_odd_len_mismatch() {
+        let options = super::DecodeOptions {
+            pad_odd: true,
+            ..super::DecodeOptions::default()
+        };
+        let mut buffer = [0u8; 2];
+        assert_eq!(
+            super::decode_to_buf_with("abc", &mut buffer[..1], options),
+            Err(super::Error::InvalidOutputLength {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(super::decode_with("", options), Ok(Vec::new()));
+        assert_eq!(
+            super::decode_with("abc", super::DecodeOptions::default()),
+            Err(super::Error::OddLength)
+        );
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_decode_nibbles(
+        #[strategy(proptest::collection::vec(
+            proptest::sample::select(b"0123456789abcdefABCDEFg".to_vec()),
+            0..2000,
+        ))]
+        input: Vec<u8>,
+    ) {
+        let expected = input
+            .iter()
+            .enumerate()
+            .map(|(index, &byte)| match (byte as char).to_digit(16) {
+                Some(nibble) => Ok(nibble as u8),
+                None => Err(super::Error::InvalidChar { byte, index }),
+            })
+            .collect();
+        prop_assert_eq!(super::decode_nibbles(&input), expected);
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_decode_in_place(
+        #[strategy(proptest::collection::vec(any::<u8>(), 0..3000))] input: Vec<
+            u8,
+        >,
+        upper: bool,
+    ) {
+        let encoded = match upper {
+            false => super::encode(&input),
+            true => super::encode_upper(&input),
+        };
+        let mut buf = encoded.clone().into_bytes();
+        prop_assert_eq!(&super::decode_in_place(&mut buf)?[..], &input[..]);
+        prop_assert_eq!(
+            &buf[input.len()..],
+            &encoded.as_bytes()[input.len()..]
+        );
+
+        let decoded = super::decode_vec(encoded.into_bytes())?;
+        prop_assert_eq!(&decoded, &input);
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_decode_in_place_reports_index(
+        #[strategy(proptest::collection::vec(any::<u8>(), 1..3000))] input: Vec<
+            u8,
+        >,
+        #[strategy(0..#input.len() * 2)] at: usize,
+    ) {
+        let mut buf = super::encode(&input).into_bytes();
+        buf[at] = b'g';
+        let expected = super::Error::InvalidChar {
+            byte: b'g',
+            index: at,
+        };
+        prop_assert_eq!(super::decode_vec(buf.clone()), Err(expected));
+        prop_assert_eq!(super::decode_in_place(&mut buf), Err(expected));
+
+        buf.push(b'0');
+        prop_assert_eq!(
+            super::decode_in_place(&mut buf),
+            Err(super::Error::OddLength)
+        );
+    }
+
     #[test]
     fn test_decode_to_slice_len_mismatch() {
         let mut buffer = [0u8; 1];
         let err =
             super::decode_to_slice("00", &mut buffer[..0]).expect_err("err");
-        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err,
+            super::Error::InvalidOutputLength {
+                expected: 1,
+                actual: 0
+            }
+        );
+        #[cfg(feature = "std")]
+        assert_eq!(
+            std::io::Error::from(err).kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_decode_reports_first_invalid_char(mut input: String) {
+        if input.len() % 2 != 0 {
+            input.push('0');
+        }
+
+        let expected = input
+            .bytes()
+            .enumerate()
+            .find(|(_, byte)| !byte.is_ascii_hexdigit())
+            .map(|(index, byte)| super::Error::InvalidChar { byte, index });
+
+        prop_assert_eq!(super::decode(&input).err(), expected);
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = super::decode("00zz").unwrap_err();
+        assert_eq!(
+            err,
+            super::Error::InvalidChar {
+                byte: b'z',
+                index: 2
+            }
+        );
+        assert_eq!(err.to_string(), "invalid hex character 'z' at index 2");
+
+        let err = super::decode("ü").unwrap_err();
+        assert_eq!(err.to_string(), "invalid hex character '\\xc3' at index 0");
+
+        let err = super::decode("000").unwrap_err();
+        assert_eq!(err, super::Error::OddLength);
+        assert_eq!(err.to_string(), "hex string length must be even");
+
+        let err = super::decode_prefixed("abcd", super::Prefix::Required)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "missing hex prefix at index 0");
     }
 
     /// Regression test: characters ':' through '@' (0x3A-0x40) must be
@@ -0,0 +1,227 @@
+/// The letter case a decoder accepts for the hex digits `a`-`f`, see
+/// [`DecodeOptions`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Case {
+    /// Only lowercase `a`-`f`, rejecting `A`-`F` as invalid characters.
+    Lower,
+    /// Only uppercase `A`-`F`, rejecting `a`-`f` as invalid characters.
+    Upper,
+    /// Either case, even mixed within one input.
+    #[default]
+    Any,
+}
+
+impl Case {
+    pub(crate) const fn accepts_lower(self) -> bool {
+        matches!(self, Case::Lower | Case::Any)
+    }
+
+    pub(crate) const fn accepts_upper(self) -> bool {
+        matches!(self, Case::Upper | Case::Any)
+    }
+}
+
+/// Options for [`decode_with`](crate::decode_with) and
+/// [`decode_to_buf_with`](crate::decode_to_buf_with).
+///
+/// The default accepts the same inputs as [`decode`](crate::decode).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DecodeOptions {
+    /// The letter case to accept.
+    pub case: Case,
+    /// Decode odd-length input as if it had a leading `0` digit, so `"abc"`
+    /// decodes like `"0abc"`, as for Ethereum quantities such as `0x1`.
+    /// Otherwise odd-length input fails with
+    /// [`Error::OddLength`](crate::Error::OddLength).
+    pub pad_odd: bool,
+}
+
+/// Whether [`decode_prefixed`](crate::decode_prefixed) requires its input
+/// to start with `0x` or `0X`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Prefix {
+    /// Strip the prefix if there is one, and decode the input as is if not.
+    #[default]
+    Optional,
+    /// Fail with [`Error::MissingPrefix`](crate::Error::MissingPrefix)
+    /// unless the input starts with the prefix.
+    Required,
+}
+
+/// Options for [`encode_with`](crate::encode_with) and
+/// [`encode_with_to_buf`](crate::encode_with_to_buf), adding separators
+/// between groups of bytes and breaking the output into lines.
+///
+/// The default produces the same output as [`encode`](crate::encode).
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use muhex::EncodeOptions;
+///
+/// let mac = EncodeOptions::new().separator(b':').upper(true);
+/// assert_eq!(muhex::encode_with([0x00, 0x1a, 0x2b], mac), "00:1A:2B");
+///
+/// let words = EncodeOptions::new().separator(b' ').group_size(2);
+/// let hex = muhex::encode_with([0xaa, 0xbb, 0xcc, 0xdd], words);
+/// assert_eq!(hex, "aabb ccdd");
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodeOptions {
+    pub(crate) separator: Option<u8>,
+    pub(crate) group_size: usize,
+    pub(crate) line_width: Option<usize>,
+    pub(crate) upper: bool,
+}
+
+impl EncodeOptions {
+    /// Creates options for plain lowercase hex without separators.
+    pub const fn new() -> Self {
+        Self {
+            separator: None,
+            group_size: 1,
+            line_width: None,
+            upper: false,
+        }
+    }
+
+    /// Writes `separator` between groups of bytes.
+    pub const fn separator(mut self, separator: u8) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+
+    /// Sets how many bytes go between two separators, 1 by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `group_size` is 0.
+    pub const fn group_size(mut self, group_size: usize) -> Self {
+        assert!(group_size > 0, "group size must not be 0");
+        self.group_size = group_size;
+        self
+    }
+
+    /// Breaks the output with a `\n` after every `line_width` bytes, in
+    /// place of a separator. Groups restart on every line, and there is no
+    /// newline after the last line.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_width` is 0.
+    pub const fn line_width(mut self, line_width: usize) -> Self {
+        assert!(line_width > 0, "line width must not be 0");
+        self.line_width = Some(line_width);
+        self
+    }
+
+    /// Emits uppercase hex digits if `upper` is true.
+    pub const fn upper(mut self, upper: bool) -> Self {
+        self.upper = upper;
+        self
+    }
+
+    /// Returns the exact length of `len` bytes encoded with these options.
+    ///
+    /// ```
+    /// use muhex::EncodeOptions;
+    ///
+    /// let options = EncodeOptions::new().separator(b':').line_width(4);
+    /// assert_eq!(options.encoded_len(6), "00:00:00:00\n00:00".len());
+    /// ```
+    pub const fn encoded_len(&self, len: usize) -> usize {
+        let Some(width) = self.line_width else {
+            return self.line_len(len);
+        };
+        if len == 0 {
+            return 0;
+        }
+
+        let (full, rest) = (len / width, len % width);
+        let lines = full + (rest > 0) as usize;
+        full * self.line_len(width) + self.line_len(rest) + lines - 1
+    }
+
+    /// Length of a single line holding `len` bytes.
+    pub(crate) const fn line_len(&self, len: usize) -> usize {
+        match self.separator {
+            Some(_) if len > 0 => len * 2 + (len - 1) / self.group_size,
+            _ => len * 2,
+        }
+    }
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A set of ASCII bytes for [`decode_lenient`](crate::decode_lenient) to
+/// skip.
+///
+/// The default skips ASCII whitespace and the common separators `:`, `-`
+/// and `_`.
+///
+/// ```
+/// use muhex::IgnoreSet;
+///
+/// let ignore = IgnoreSet::WHITESPACE.with(b'.');
+/// assert!(ignore.contains(b'\n') && ignore.contains(b'.'));
+/// assert!(!ignore.contains(b':'));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IgnoreSet {
+    /// Bit `b >> 4` of `rows[b & 0xf]` is set if `b` is ignored, the layout
+    /// the SIMD kernels look bytes up in.
+    pub(crate) rows: [u8; 16],
+}
+
+impl IgnoreSet {
+    /// Ignores nothing, like the strict decoders.
+    pub const NONE: Self = Self { rows: [0; 16] };
+
+    /// ASCII whitespace, as in [`u8::is_ascii_whitespace`].
+    pub const WHITESPACE: Self = Self::NONE
+        .with(b' ')
+        .with(b'\t')
+        .with(b'\n')
+        .with(b'\x0C')
+        .with(b'\r');
+
+    /// The separators `:`, `-` and `_`.
+    pub const SEPARATORS: Self = Self::NONE.with(b':').with(b'-').with(b'_');
+
+    /// Adds `byte` to the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte` is not ASCII.
+    pub const fn with(mut self, byte: u8) -> Self {
+        assert!(byte.is_ascii(), "ignored bytes must be ASCII");
+        self.rows[(byte & 0xf) as usize] |= 1 << (byte >> 4);
+        self
+    }
+
+    /// Returns the bytes in either set.
+    pub const fn union(mut self, other: Self) -> Self {
+        let mut i = 0;
+        while i < 16 {
+            self.rows[i] |= other.rows[i];
+            i += 1;
+        }
+        self
+    }
+
+    /// Returns whether `byte` is in the set.
+    pub const fn contains(&self, byte: u8) -> bool {
+        byte.is_ascii()
+            && self.rows[(byte & 0xf) as usize] & 1 << (byte >> 4) != 0
+    }
+}
+
+impl Default for IgnoreSet {
+    fn default() -> Self {
+        Self::WHITESPACE.union(Self::SEPARATORS)
+    }
+}
@@ -0,0 +1,94 @@
+// Shared by the line-based text formats: dumps, Intel HEX and S-records.
+
+use core::fmt;
+
+/// An error in a line-based text format, at a 1-based line and column of
+/// the input.
+///
+/// `K` says what is wrong, and differs between formats: see
+/// [`dump::ParseErrorKind`](crate::dump::ParseErrorKind) and
+/// [`ihex::ParseErrorKind`](crate::ihex::ParseErrorKind).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseError<K> {
+    /// The line of the error, counting from 1.
+    pub line: usize,
+    /// The byte of the line where the error is, counting from 1.
+    pub column: usize,
+    /// What is wrong there.
+    pub kind: K,
+}
+
+impl<K> ParseError<K> {
+    /// Creates an error at the 0-based `index` of a line and `column`.
+    pub(crate) fn new(index: usize, column: usize, kind: K) -> Self {
+        Self {
+            line: index + 1,
+            column: column + 1,
+            kind,
+        }
+    }
+}
+
+impl<K: fmt::Display> fmt::Display for ParseError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl<K: fmt::Debug + fmt::Display> core::error::Error for ParseError<K> {}
+
+#[cfg(feature = "std")]
+impl<K> From<ParseError<K>> for std::io::Error
+where
+    K: fmt::Debug + fmt::Display + Send + Sync + 'static,
+{
+    fn from(err: ParseError<K>) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Iterates over the lines of `input` that are not blank, with their
+/// 0-based index and without surrounding whitespace.
+pub(crate) fn lines(input: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    input
+        .split(|&byte| byte == b'\n')
+        .map(<[u8]>::trim_ascii)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{ParseError, lines};
+
+    /// Builds the error expected at the 1-based `line` and `column`.
+    pub(crate) fn error<K>(
+        line: usize,
+        column: usize,
+        kind: K,
+    ) -> ParseError<K> {
+        ParseError { line, column, kind }
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        assert_eq!(
+            error(3, 7, "bad byte").to_string(),
+            "line 3, column 7: bad byte"
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let input = b"a\r\n\n  b  \n\t\nc";
+        assert_eq!(
+            lines(input).collect::<Vec<_>>(),
+            [(0, &b"a"[..]), (2, b"b"), (4, b"c")]
+        );
+    }
+}
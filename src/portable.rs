@@ -0,0 +1,621 @@
+// Portable `std::simd` backend, used on targets without x86_64 intrinsics
+// when the nightly `portable-simd` feature is enabled.
+
+use core::{
+    mem::MaybeUninit,
+    simd::{
+        Select,
+        Simd,
+        cmp::{SimdPartialEq, SimdPartialOrd},
+        simd_swizzle,
+        u8x16,
+        u8x32,
+        u8x64,
+    },
+};
+
+use crate::{
+    COMPACT_SHUFFLES,
+    Case,
+    Error,
+    GroupShuffle,
+    IgnoreSet,
+    compact_scalar,
+    decode_remainder_lut,
+    encode_grouped_with,
+    encode_scalar,
+    invalid_hex_char_error,
+};
+
+type SimdU8<const LANES: usize> = Simd<u8, LANES>;
+
+// ─── Encode ─────────────────────────────────────────────────────────────
+
+#[inline(always)]
+fn encode_simd_32(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+) {
+    let raw: u8x32 = Simd::from_slice(input);
+
+    let high_nibble = raw >> Simd::splat(4);
+    let low_nibble = raw & Simd::splat(0x0F);
+
+    let bias_0 = Simd::splat(b'0');
+    let bias_a = Simd::splat(lut[10] - 10);
+    let cmp_9 = Simd::splat(9u8);
+
+    let hi_ascii = nibble_to_ascii_32(high_nibble, bias_0, bias_a, cmp_9);
+    let lo_ascii = nibble_to_ascii_32(low_nibble, bias_0, bias_a, cmp_9);
+
+    let interleaved: u8x64 = simd_swizzle!(
+        hi_ascii,
+        lo_ascii,
+        [
+            0, 32, 1, 33, 2, 34, 3, 35, 4, 36, 5, 37, 6, 38, 7, 39, 8, 40, 9,
+            41, 10, 42, 11, 43, 12, 44, 13, 45, 14, 46, 15, 47, 16, 48, 17, 49,
+            18, 50, 19, 51, 20, 52, 21, 53, 22, 54, 23, 55, 24, 56, 25, 57, 26,
+            58, 27, 59, 28, 60, 29, 61, 30, 62, 31, 63
+        ]
+    );
+
+    let interleaved: &[u8; 64] = interleaved.as_array();
+    let uninit_src: &[MaybeUninit<u8>; 64] =
+        unsafe { core::mem::transmute(interleaved) };
+    output.copy_from_slice(uninit_src);
+}
+
+#[inline(always)]
+fn encode_simd_16(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+) {
+    let raw: u8x16 = Simd::from_slice(input);
+
+    let high_nibble = raw >> Simd::splat(4);
+    let low_nibble = raw & Simd::splat(0x0F);
+
+    let bias_0 = Simd::splat(b'0');
+    let bias_a = Simd::splat(lut[10] - 10);
+    let cmp_9 = Simd::splat(9u8);
+
+    let hi_ascii = nibble_to_ascii(high_nibble, bias_0, bias_a, cmp_9);
+    let lo_ascii = nibble_to_ascii(low_nibble, bias_0, bias_a, cmp_9);
+
+    let interleaved: u8x32 = simd_swizzle!(
+        hi_ascii,
+        lo_ascii,
+        [
+            0, 16, 1, 17, 2, 18, 3, 19, 4, 20, 5, 21, 6, 22, 7, 23, 8, 24, 9,
+            25, 10, 26, 11, 27, 12, 28, 13, 29, 14, 30, 15, 31
+        ]
+    );
+
+    let interleaved: &[u8; 32] = interleaved.as_array();
+    let uninit_src: &[MaybeUninit<u8>; 32] =
+        unsafe { core::mem::transmute(interleaved) };
+    output.copy_from_slice(uninit_src);
+}
+
+#[inline]
+pub(crate) fn encode(data: &[u8], dst: &mut [MaybeUninit<u8>], lut: &[u8; 16]) {
+    let mut pos = 0;
+
+    while pos + 32 <= data.len() {
+        encode_simd_32(
+            &data[pos..pos + 32],
+            &mut dst[pos * 2..(pos + 32) * 2],
+            lut,
+        );
+        pos += 32;
+    }
+
+    if pos < data.len() {
+        if data.len() >= 32 {
+            let start = data.len() - 32;
+            encode_simd_32(&data[start..], &mut dst[start * 2..], lut);
+        } else if data.len() >= 16 {
+            encode_simd_16(&data[0..16], &mut dst[0..32], lut);
+            if data.len() > 16 {
+                let start = data.len() - 16;
+                encode_simd_16(&data[start..], &mut dst[start * 2..], lut);
+            }
+        } else {
+            encode_scalar(&data[pos..], &mut dst[pos * 2..], lut);
+        }
+    }
+}
+
+/// Encodes 16 bytes like `encode_simd_16`, then spreads the digits out with
+/// `shuffle` to insert `separator` after every group, writing 48 bytes of
+/// which the first `shuffle.len` are meaningful.
+#[inline(always)]
+fn encode_grouped_simd_16(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+    shuffle: &GroupShuffle,
+    separator: u8x16,
+) {
+    let raw: u8x16 = Simd::from_slice(input);
+
+    let high_nibble = raw >> Simd::splat(4);
+    let low_nibble = raw & Simd::splat(0x0F);
+
+    let bias_0 = Simd::splat(b'0');
+    let bias_a = Simd::splat(lut[10] - 10);
+    let cmp_9 = Simd::splat(9u8);
+
+    let hi_ascii = nibble_to_ascii(high_nibble, bias_0, bias_a, cmp_9);
+    let lo_ascii = nibble_to_ascii(low_nibble, bias_0, bias_a, cmp_9);
+
+    let interleaved_lo: u8x16 = simd_swizzle!(
+        hi_ascii,
+        lo_ascii,
+        [0, 16, 1, 17, 2, 18, 3, 19, 4, 20, 5, 21, 6, 22, 7, 23]
+    );
+    let interleaved_hi: u8x16 = simd_swizzle!(
+        hi_ascii,
+        lo_ascii,
+        [8, 24, 9, 25, 10, 26, 11, 27, 12, 28, 13, 29, 14, 30, 15, 31]
+    );
+
+    let (blocks, _) = output.as_chunks_mut::<16>();
+    for (i, output) in blocks.iter_mut().take(3).enumerate() {
+        let from_lo =
+            interleaved_lo.swizzle_dyn(u8x16::from_array(shuffle.lo[i]));
+        let from_hi =
+            interleaved_hi.swizzle_dyn(u8x16::from_array(shuffle.hi[i]));
+        let separators = u8x16::from_array(shuffle.sep[i]) & separator;
+
+        let block = (from_lo | from_hi | separators).to_array();
+        *output = block.map(MaybeUninit::new);
+    }
+}
+
+#[inline]
+pub(crate) fn encode_grouped(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+    group: usize,
+    separator: u8,
+) {
+    let mut pos = 0;
+    let mut out = 0;
+
+    if let Some(shuffle) = GroupShuffle::for_group(group) {
+        let separator = Simd::splat(separator);
+
+        // The 48-byte writes may run past the block, but only into output
+        // that later blocks or the tail overwrite
+        while pos + 16 <= data.len() && out + 48 <= dst.len() {
+            encode_grouped_simd_16(
+                &data[pos..pos + 16],
+                &mut dst[out..out + 48],
+                lut,
+                shuffle,
+                separator,
+            );
+            pos += 16;
+            out += shuffle.len;
+        }
+    }
+
+    encode_grouped_with(
+        &data[pos..],
+        &mut dst[out..],
+        lut,
+        group,
+        separator,
+        encode,
+    );
+}
+
+#[inline(always)]
+fn nibble_to_ascii(
+    n: u8x16,
+    bias_0: u8x16,
+    bias_a: u8x16,
+    cmp_9: u8x16,
+) -> u8x16 {
+    let mask_gt_9 = n.simd_gt(cmp_9);
+    let base_0 = n + bias_0;
+    let base_a = n + bias_a;
+    mask_gt_9.select(base_a, base_0)
+}
+
+#[inline(always)]
+fn nibble_to_ascii_32(
+    n: u8x32,
+    bias_0: u8x32,
+    bias_a: u8x32,
+    cmp_9: u8x32,
+) -> u8x32 {
+    let mask_gt_9 = n.simd_gt(cmp_9);
+    let base_0 = n + bias_0;
+    let base_a = n + bias_a;
+    mask_gt_9.select(base_a, base_0)
+}
+
+// ─── Compaction ─────────────────────────────────────────────────────────
+
+/// Copies the bytes of a 16-byte block not in `ignore` to `output`,
+/// returning how many there were. Writes 16 bytes, of which only that many
+/// are meaningful.
+#[inline(always)]
+fn compact_simd_16(input: &[u8], output: &mut [u8], rows: u8x16) -> usize {
+    let raw: u8x16 = Simd::from_slice(input);
+    let bits = u8x16::from_array([
+        1, 2, 4, 8, 16, 32, 64, 128, 0, 0, 0, 0, 0, 0, 0, 0,
+    ]);
+
+    // High nibbles 8-15 select no bit, so non-ASCII bytes are always kept
+    let ignored = rows.swizzle_dyn(raw & Simd::splat(0x0F))
+        & bits.swizzle_dyn(raw >> Simd::splat(4));
+    let keep = ignored.simd_eq(Simd::splat(0)).to_bitmask() as usize;
+
+    if keep == 0xFFFF {
+        output[..16].copy_from_slice(raw.as_array());
+        return 16;
+    }
+
+    let (keep_lo, keep_hi) = (keep & 0xFF, keep >> 8);
+    let mut shuffle = [0x80; 16];
+    shuffle[..8].copy_from_slice(&COMPACT_SHUFFLES[keep_lo]);
+    shuffle[8..].copy_from_slice(&COMPACT_SHUFFLES[keep_hi]);
+    // The upper half picks from the upper 8 bytes of the block
+    let shuffle = u8x16::from_array(shuffle)
+        + u8x16::from_array([0, 0, 0, 0, 0, 0, 0, 0, 8, 8, 8, 8, 8, 8, 8, 8]);
+    let compacted = raw.swizzle_dyn(shuffle).to_array();
+
+    let len_lo = keep_lo.count_ones() as usize;
+    output[..8].copy_from_slice(&compacted[..8]);
+    output[len_lo..len_lo + 8].copy_from_slice(&compacted[8..]);
+
+    len_lo + keep_hi.count_ones() as usize
+}
+
+#[inline]
+pub(crate) fn compact(
+    input: &[u8],
+    output: &mut [u8],
+    ignore: &IgnoreSet,
+) -> usize {
+    let rows = u8x16::from_array(ignore.rows);
+    let mut pos = 0;
+    let mut len = 0;
+
+    // Every write ends at most 16 bytes past `len <= pos`, so within the
+    // `output.len() >= input.len()` bytes
+    while pos + 16 <= input.len() {
+        len += compact_simd_16(&input[pos..pos + 16], &mut output[len..], rows);
+        pos += 16;
+    }
+
+    len + compact_scalar(&input[pos..], &mut output[len..], ignore)
+}
+
+// ─── Decode ─────────────────────────────────────────────────────────────
+
+#[inline(always)]
+fn decode_simd_64(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> bool {
+    let chunk_vec: SimdU8<64> = Simd::from_slice(input);
+
+    let high_bytes: SimdU8<32> = simd_swizzle!(
+        chunk_vec,
+        [
+            0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30, 32, 34,
+            36, 38, 40, 42, 44, 46, 48, 50, 52, 54, 56, 58, 60, 62
+        ]
+    );
+    let low_bytes: SimdU8<32> = simd_swizzle!(
+        chunk_vec,
+        [
+            1, 3, 5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25, 27, 29, 31, 33, 35,
+            37, 39, 41, 43, 45, 47, 49, 51, 53, 55, 57, 59, 61, 63
+        ]
+    );
+
+    let (high_nibbles, high_valid) = decode_hex_nibbles(high_bytes, case);
+    let (low_nibbles, low_valid) = decode_hex_nibbles(low_bytes, case);
+
+    if !(high_valid & low_valid) {
+        return false;
+    }
+    let decoded = (high_nibbles << SimdU8::<32>::splat(4)) | low_nibbles;
+
+    let decoded: &[u8; 32] = decoded.as_array();
+    let uninit_src: &[MaybeUninit<u8>; 32] =
+        unsafe { core::mem::transmute(decoded) };
+    output.copy_from_slice(uninit_src);
+    true
+}
+
+#[inline(always)]
+fn decode_simd_32(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> bool {
+    let chunk_vec: SimdU8<32> = Simd::from_slice(input);
+    let high_bytes: SimdU8<16> = simd_swizzle!(
+        chunk_vec,
+        [0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30]
+    );
+    let low_bytes: SimdU8<16> = simd_swizzle!(
+        chunk_vec,
+        [1, 3, 5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25, 27, 29, 31]
+    );
+
+    let (high_nibbles, high_valid) = decode_hex_nibbles(high_bytes, case);
+    let (low_nibbles, low_valid) = decode_hex_nibbles(low_bytes, case);
+
+    if !(high_valid & low_valid) {
+        return false;
+    }
+
+    let decoded = (high_nibbles << SimdU8::<16>::splat(4)) | low_nibbles;
+    let decoded: &[u8; 16] = decoded.as_array();
+    let uninit_src: &[MaybeUninit<u8>; 16] =
+        unsafe { core::mem::transmute(decoded) };
+    output.copy_from_slice(uninit_src);
+    true
+}
+
+#[inline]
+pub(crate) fn decode(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    let n = input.len();
+    let mut pos = 0;
+    let mut out_pos = 0;
+
+    while pos + 64 <= n {
+        if !decode_simd_64(
+            &input[pos..pos + 64],
+            &mut output[out_pos..out_pos + 32],
+            case,
+        ) {
+            return Err(invalid_hex_char_error(input, pos, case));
+        }
+        pos += 64;
+        out_pos += 32;
+    }
+
+    if pos < n {
+        if n >= 64 {
+            let start = n - 64;
+            let out_start = start / 2;
+            if !decode_simd_64(
+                &input[start..start + 64],
+                &mut output[out_start..out_start + 32],
+                case,
+            ) {
+                return Err(invalid_hex_char_error(input, start, case));
+            }
+        } else {
+            while pos + 32 <= n {
+                if !decode_simd_32(
+                    &input[pos..pos + 32],
+                    &mut output[out_pos..out_pos + 16],
+                    case,
+                ) {
+                    return Err(invalid_hex_char_error(input, pos, case));
+                }
+                pos += 32;
+                out_pos += 16;
+            }
+            if pos < n {
+                if n >= 32 {
+                    let start = n - 32;
+                    let out_start = start / 2;
+                    if !decode_simd_32(
+                        &input[start..start + 32],
+                        &mut output[out_start..out_start + 16],
+                        case,
+                    ) {
+                        return Err(invalid_hex_char_error(input, start, case));
+                    }
+                } else {
+                    let remaining = n - pos;
+                    decode_remainder_lut(
+                        input, output, pos, out_pos, remaining, case,
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps hex chars to their nibble values, also returning whether all of
+/// them are hex digits in `case`.
+///
+/// Digits and letters are validated with unsigned range checks after
+/// rebasing them to zero. With [`Case::Any`], letters are folded to
+/// lowercase first by setting bit 5.
+#[inline(always)]
+fn decode_hex_nibbles<const LANES: usize>(
+    n: SimdU8<LANES>,
+    case: Case,
+) -> (SimdU8<LANES>, bool) {
+    let (fold, first_letter) = match case {
+        Case::Lower => (0, b'a'),
+        Case::Upper => (0, b'A'),
+        Case::Any => (0x20, b'a'),
+    };
+
+    let digits = n - SimdU8::<LANES>::splat(b'0');
+    let letters = (n | SimdU8::<LANES>::splat(fold))
+        - SimdU8::<LANES>::splat(first_letter);
+
+    let is_digit = digits.simd_le(SimdU8::<LANES>::splat(9));
+    let is_letter = letters.simd_le(SimdU8::<LANES>::splat(5));
+    let val = is_digit.select(digits, letters + SimdU8::<LANES>::splat(10));
+
+    (val, (is_digit | is_letter).all())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+
+    use proptest::{collection::vec, prelude::*};
+
+    use crate::{
+        Buf,
+        Case,
+        Error,
+        HEX_ENCODE_LUT,
+        HEX_ENCODE_LUT_UPPER,
+        IgnoreSet,
+        encode_grouped_reference,
+        lenient_into,
+        swar,
+    };
+
+    type Compact = fn(&[u8], &mut [u8], &IgnoreSet) -> usize;
+    type Decode = fn(&[u8], &mut [MaybeUninit<u8>], Case) -> Result<(), Error>;
+
+    fn decode_with(
+        decode: Decode,
+        input: &[u8],
+        case: Case,
+    ) -> Result<Vec<u8>, Error> {
+        let mut output = vec![0u8; input.len() / 2];
+        decode(input, unsafe { output.dst() }, case)?;
+        Ok(output)
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_portable_encode_parity(input: Vec<u8>, upper: bool) {
+        let lut = match upper {
+            false => &HEX_ENCODE_LUT,
+            true => &HEX_ENCODE_LUT_UPPER,
+        };
+        let mut output = vec![0u8; input.len() * 2];
+        super::encode(&input, unsafe { output.dst() }, lut);
+        let mut expected = vec![0u8; input.len() * 2];
+        swar::encode(&input, unsafe { expected.dst() }, lut);
+        prop_assert_eq!(output, expected);
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_portable_encode_grouped_parity(
+        #[strategy(vec(any::<u8>(), 0..300))] input: Vec<u8>,
+        #[strategy(1usize..40)] group: usize,
+        separator: u8,
+        upper: bool,
+    ) {
+        let lut = match upper {
+            false => &HEX_ENCODE_LUT,
+            true => &HEX_ENCODE_LUT_UPPER,
+        };
+        let expected = encode_grouped_reference(&input, lut, group, separator);
+        let mut output = vec![0u8; expected.len()];
+        let dst = unsafe { output.dst() };
+        super::encode_grouped(&input, dst, lut, group, separator);
+        prop_assert_eq!(output, expected);
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_portable_compact_parity(
+        #[strategy(vec(
+            proptest::sample::select(b"0123456789abcdef :-\n\xff".to_vec()),
+            0..300,
+        ))]
+        input: Vec<u8>,
+        ignore_digits: bool,
+    ) {
+        let mut ignore = IgnoreSet::default();
+        if ignore_digits {
+            ignore = ignore.with(b'0');
+        }
+
+        let mut output = vec![0u8; input.len()];
+        let len = super::compact(&input, &mut output, &ignore);
+        let mut expected = vec![0u8; input.len()];
+        let expected_len = swar::compact(&input, &mut expected, &ignore);
+        prop_assert_eq!(&output[..len], &expected[..expected_len]);
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_portable_lenient_parity(
+        #[strategy(vec(
+            prop_oneof![
+                8 => proptest::sample::select(b"0123456789abcdefABCDEF".to_vec()),
+                4 => proptest::sample::select(b" :-_\n\t".to_vec()),
+                1 => any::<u8>(),
+            ],
+            0..1500,
+        ))]
+        input: Vec<u8>,
+        #[strategy(0usize..800)] output_len: usize,
+    ) {
+        let ignore = IgnoreSet::default();
+        let lenient = |case, compact: Compact, decode: Decode| {
+            let mut output = vec![0u8; output_len];
+            let dst = unsafe { output.dst() };
+            let len =
+                lenient_into(&input, dst, &ignore, case, compact, decode)?;
+            output.truncate(len);
+            Ok::<_, Error>(output)
+        };
+
+        for case in [Case::Lower, Case::Upper, Case::Any] {
+            prop_assert_eq!(
+                lenient(case, super::compact, super::decode),
+                lenient(case, swar::compact, swar::decode)
+            );
+        }
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_portable_decode_parity(
+        #[strategy(vec(
+            prop_oneof![
+                8 => proptest::sample::select(
+                    b"0123456789abcdefABCDEF".to_vec()
+                ),
+                1 => any::<u8>(),
+            ],
+            0..300,
+        ))]
+        input: Vec<u8>,
+    ) {
+        let input = &input[..input.len() & !1];
+        for case in [Case::Lower, Case::Upper, Case::Any] {
+            prop_assert_eq!(
+                decode_with(super::decode, input, case),
+                decode_with(swar::decode, input, case),
+                "case {:?}",
+                case
+            );
+        }
+    }
+
+    #[test]
+    fn test_portable_reports_invalid_char_index() {
+        for at in 0..128 {
+            let mut input = [b'a'; 128];
+            input[at] = b'g';
+            assert_eq!(
+                decode_with(super::decode, &input, Case::Any),
+                Err(Error::InvalidChar {
+                    byte: b'g',
+                    index: at
+                })
+            );
+        }
+    }
+}
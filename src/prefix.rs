@@ -0,0 +1,263 @@
+// `0x`-prefixed hex, as in Ethereum and C integer literals, and `\xNN`
+// escapes, as in C and Python byte strings.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    Buf,
+    Case,
+    DecodeOptions,
+    Error,
+    HEX_ENCODE_LUT,
+    Prefix,
+    backend,
+    decode_bytes,
+    decode_into,
+};
+
+/// Bytes escaped or unescaped per round, bounding the stack buffers.
+const ESCAPE_CHUNK_LEN: usize = 256;
+
+/// Like [`encode`](crate::encode), but starts the output with `0x`.
+///
+/// ```
+/// assert_eq!(muhex::encode_prefixed([0xde, 0xad]), "0xdead");
+/// ```
+#[inline]
+pub fn encode_prefixed<T: AsRef<[u8]>>(v: T) -> String {
+    let data = v.as_ref();
+    let mut result = Vec::with_capacity(data.len() * 2 + 2);
+    result.extend_from_slice(b"0x");
+    backend::encode(
+        data,
+        &mut result.spare_capacity_mut()[..data.len() * 2],
+        &HEX_ENCODE_LUT,
+    );
+    unsafe {
+        result.set_len(data.len() * 2 + 2);
+    }
+    unsafe { String::from_utf8_unchecked(result) }
+}
+
+/// Like [`decode`](crate::decode), but strips a leading `0x` or `0X`
+/// according to `prefix`.
+///
+/// Invalid characters are reported at their index in `input`, counting the
+/// prefix.
+///
+/// ```
+/// use muhex::Prefix;
+///
+/// let bytes = muhex::decode_prefixed("0xdead", Prefix::Required)?;
+/// assert_eq!(bytes, [0xde, 0xad]);
+/// assert_eq!(muhex::decode_prefixed("dead", Prefix::Optional)?, bytes);
+/// assert!(muhex::decode_prefixed("dead", Prefix::Required).is_err());
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[inline]
+pub fn decode_prefixed<T: AsRef<[u8]>>(
+    input: T,
+    prefix: Prefix,
+) -> Result<Vec<u8>, Error> {
+    let (hex, offset) = strip_prefix(input.as_ref(), prefix)?;
+
+    decode_bytes(hex, DecodeOptions::default()).map_err(|err| match err {
+        Error::InvalidChar { byte, index } => Error::InvalidChar {
+            byte,
+            index: index + offset,
+        },
+        err => err,
+    })
+}
+
+/// Strips the `0x` or `0X` prefix from `input` according to `prefix`,
+/// returning the rest and the length of the prefix.
+#[inline]
+pub(crate) fn strip_prefix(
+    input: &[u8],
+    prefix: Prefix,
+) -> Result<(&[u8], usize), Error> {
+    match (input, prefix) {
+        ([b'0', b'x' | b'X', hex @ ..], _) => Ok((hex, 2)),
+        (_, Prefix::Optional) => Ok((input, 0)),
+        (_, Prefix::Required) => Err(Error::MissingPrefix { index: 0 }),
+    }
+}
+
+/// Encodes every byte as a `\xNN` escape, as in C and Python byte strings.
+///
+/// ```
+/// assert_eq!(muhex::encode_escaped(b"\x00hi"), r"\x00\x68\x69");
+/// ```
+#[inline]
+pub fn encode_escaped<T: AsRef<[u8]>>(v: T) -> String {
+    let data = v.as_ref();
+    let mut result = Vec::with_capacity(data.len() * 4);
+    let mut hex = [0u8; ESCAPE_CHUNK_LEN * 2];
+
+    for chunk in data.chunks(ESCAPE_CHUNK_LEN) {
+        let hex = &mut hex[..chunk.len() * 2];
+        // SAFETY: `backend::encode` only writes initialized bytes
+        backend::encode(chunk, unsafe { hex.dst() }, &HEX_ENCODE_LUT);
+
+        for digits in hex.as_chunks::<2>().0 {
+            result.extend_from_slice(&[b'\\', b'x', digits[0], digits[1]]);
+        }
+    }
+
+    unsafe { String::from_utf8_unchecked(result) }
+}
+
+/// Decodes input made of `\xNN` escapes only, as produced by
+/// [`encode_escaped`].
+///
+/// Fails with [`Error::MissingPrefix`] where an escape does not start with
+/// `\x`, and with [`Error::OddLength`] if the input ends inside an escape.
+/// Invalid digits are reported at their index in `input`.
+///
+/// ```
+/// assert_eq!(muhex::decode_escaped(r"\xde\xAD")?, [0xde, 0xad]);
+/// assert!(muhex::decode_escaped(r"\xde\xa").is_err());
+/// # Ok::<(), muhex::Error>(())
+/// ```
+#[inline]
+pub fn decode_escaped<T: AsRef<[u8]>>(input: T) -> Result<Vec<u8>, Error> {
+    let input = input.as_ref();
+    let (escapes, rest) = input.as_chunks::<4>();
+    let mut output = Vec::with_capacity(escapes.len());
+    let mut hex = [0u8; ESCAPE_CHUNK_LEN * 2];
+
+    for (i, chunk) in escapes.chunks(ESCAPE_CHUNK_LEN).enumerate() {
+        let start = i * ESCAPE_CHUNK_LEN;
+
+        // Decode the escapes before a missing `\x` first, so that invalid
+        // digits before it are reported first
+        let len = chunk
+            .iter()
+            .position(|escape| escape[..2] != *b"\\x")
+            .unwrap_or(chunk.len());
+        for (digits, escape) in
+            hex.as_chunks_mut::<2>().0.iter_mut().zip(&chunk[..len])
+        {
+            *digits = [escape[2], escape[3]];
+        }
+
+        let out = &mut output.spare_capacity_mut()[..len];
+        decode_into(&hex[..len * 2], out, Case::Any).map_err(
+            |err| match err {
+                // Digit `index` sits in escape `index / 2`, after its `\x`
+                Error::InvalidChar { byte, index } => Error::InvalidChar {
+                    byte,
+                    index: (start + index / 2) * 4 + 2 + index % 2,
+                },
+                err => err,
+            },
+        )?;
+        unsafe { output.set_len(start + len) };
+
+        if len < chunk.len() {
+            let index = (start + len) * 4;
+            return Err(Error::MissingPrefix { index });
+        }
+    }
+
+    match rest {
+        [] => Ok(output),
+        [b'\\'] | [b'\\', b'x', ..] => Err(Error::OddLength),
+        _ => Err(Error::MissingPrefix {
+            index: escapes.len() * 4,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::{
+        decode_escaped,
+        decode_prefixed,
+        encode_escaped,
+        encode_prefixed,
+    };
+    use crate::{Error, Prefix};
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_prefixed_roundtrip(input: Vec<u8>, upper_x: bool) {
+        let encoded = encode_prefixed(&input);
+        prop_assert_eq!(&encoded, &format!("0x{}", hex::encode(&input)));
+
+        let encoded = match upper_x {
+            false => encoded,
+            true => encoded.replacen("0x", "0X", 1),
+        };
+        for prefix in [Prefix::Optional, Prefix::Required] {
+            prop_assert_eq!(&decode_prefixed(&encoded, prefix)?, &input);
+        }
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_unprefixed(input: Vec<u8>) {
+        let encoded = hex::encode(&input);
+        prop_assert_eq!(&decode_prefixed(&encoded, Prefix::Optional)?, &input);
+        prop_assert_eq!(
+            decode_prefixed(&encoded, Prefix::Required),
+            Err(Error::MissingPrefix { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_prefixed_errors() {
+        assert_eq!(
+            decode_prefixed("0xabzz", Prefix::Required),
+            Err(Error::InvalidChar {
+                byte: b'z',
+                index: 4
+            })
+        );
+        assert_eq!(
+            decode_prefixed("0xabc", Prefix::Optional),
+            Err(Error::OddLength)
+        );
+        assert_eq!(decode_prefixed("0x", Prefix::Required), Ok(Vec::new()));
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_escaped_roundtrip(
+        #[strategy(vec(any::<u8>(), 0..1000))] input: Vec<u8>,
+    ) {
+        let encoded = encode_escaped(&input);
+        let expected: String =
+            input.iter().map(|byte| format!("\\x{byte:02x}")).collect();
+        prop_assert_eq!(&encoded, &expected);
+        prop_assert_eq!(&decode_escaped(&encoded)?, &input);
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_escaped_reports_index(
+        #[strategy(vec(any::<u8>(), 1..1000))] input: Vec<u8>,
+        #[strategy(0..#input.len() * 4)] at: usize,
+    ) {
+        let mut encoded = encode_escaped(&input).into_bytes();
+        encoded[at] = b'g';
+
+        let expected = match at % 4 {
+            0 | 1 => Error::MissingPrefix { index: at - at % 4 },
+            _ => Error::InvalidChar {
+                byte: b'g',
+                index: at,
+            },
+        };
+        prop_assert_eq!(decode_escaped(&encoded), Err(expected));
+    }
+
+    #[test]
+    fn test_escaped_truncated() {
+        assert_eq!(decode_escaped(r"\xab\x"), Err(Error::OddLength));
+        assert_eq!(decode_escaped(r"\xab\xc"), Err(Error::OddLength));
+        assert_eq!(
+            decode_escaped(r"\xab0x"),
+            Err(Error::MissingPrefix { index: 4 })
+        );
+    }
+}
@@ -1,18 +1,41 @@
-use std::fmt;
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, marker::PhantomData};
 
 use serde::{Deserializer, Serializer, de};
 
-use crate::{decode, encode};
+use crate::{
+    Error,
+    Prefix,
+    decode,
+    decode_array,
+    encode,
+    encode_prefixed,
+    encode_upper,
+    prefix::strip_prefix,
+};
 
 pub trait FromBytes {
     type Error: fmt::Display;
     fn from_bytes(bytes: Vec<u8>) -> Result<Self, Self::Error>
     where
         Self: Sized;
+
+    /// Decodes `hex` into `Self`, called by [`deserialize`].
+    ///
+    /// The default decodes into a `Vec` and passes it to
+    /// [`from_bytes`](FromBytes::from_bytes); types that know their size
+    /// can decode in place instead.
+    fn from_hex_bytes<E: de::Error>(hex: &[u8]) -> Result<Self, E>
+    where
+        Self: Sized,
+    {
+        let bytes = decode(hex).map_err(E::custom)?;
+        Self::from_bytes(bytes).map_err(E::custom)
+    }
 }
 
 impl FromBytes for Vec<u8> {
-    type Error = std::convert::Infallible;
+    type Error = core::convert::Infallible;
 
     fn from_bytes(bytes: Vec<u8>) -> Result<Self, Self::Error> {
         Ok(bytes)
@@ -27,6 +50,24 @@ impl<const N: usize> FromBytes for [u8; N] {
             format!("expected array of length {}, got length {}", N, v.len())
         })
     }
+
+    fn from_hex_bytes<E: de::Error>(hex: &[u8]) -> Result<Self, E> {
+        decode_array(hex).map_err(|err| match err {
+            Error::InvalidOutputLength { expected, actual } => {
+                E::invalid_length(expected, &ExpectedLen(actual))
+            }
+            err => E::custom(err),
+        })
+    }
+}
+
+/// Describes a hex string of the given decoded length in serde errors.
+struct ExpectedLen(usize);
+
+impl de::Expected for ExpectedLen {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a hex string of {} bytes", self.0)
+    }
 }
 
 #[inline(always)]
@@ -39,31 +80,144 @@ where
     serializer.serialize_str(&hex_string)
 }
 
+/// Like [`serialize`], but emits uppercase hex digits.
+#[inline(always)]
+pub fn serialize_upper<S, T>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<[u8]>,
+{
+    let hex_string = encode_upper(value);
+    serializer.serialize_str(&hex_string)
+}
+
 #[inline(always)]
 pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'de>,
     T: FromBytes,
 {
-    struct HexVisitor;
+    deserializer.deserialize_str(HexVisitor::<T>::new(None))
+}
+
+/// Like [`serialize`], but starts the output with `0x`.
+#[inline(always)]
+pub fn serialize_prefixed<S, T>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: AsRef<[u8]>,
+{
+    let hex_string = encode_prefixed(value);
+    serializer.serialize_str(&hex_string)
+}
+
+/// Like [`deserialize`], but strips a leading `0x` or `0X` according to
+/// `prefix`.
+#[inline(always)]
+pub fn deserialize_prefixed<'de, D, T>(
+    deserializer: D,
+    prefix: Prefix,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromBytes,
+{
+    deserializer.deserialize_str(HexVisitor::<T>::new(Some(prefix)))
+}
 
-    impl de::Visitor<'_> for HexVisitor {
-        type Value = Vec<u8>;
+struct HexVisitor<T> {
+    prefix: Option<Prefix>,
+    marker: PhantomData<T>,
+}
 
-        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a hex-encoded string")
+impl<T> HexVisitor<T> {
+    fn new(prefix: Option<Prefix>) -> Self {
+        Self {
+            prefix,
+            marker: PhantomData,
         }
+    }
+}
+
+impl<T: FromBytes> de::Visitor<'_> for HexVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.prefix {
+            Some(Prefix::Required) => {
+                formatter.write_str("a 0x-prefixed hex-encoded string")
+            }
+            _ => formatter.write_str("a hex-encoded string"),
+        }
+    }
 
-        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let hex = match self.prefix {
+            Some(prefix) => {
+                strip_prefix(value.as_bytes(), prefix).map_err(E::custom)?.0
+            }
+            None => value.as_bytes(),
+        };
+        T::from_hex_bytes(hex)
+    }
+}
+
+/// Uppercase counterpart of this module, for use with
+/// `#[serde(with = "muhex::serde::upper")]`. Deserialization accepts either
+/// case.
+pub mod upper {
+    pub use super::{deserialize, serialize_upper as serialize};
+}
+
+/// `0x`-prefixed counterpart of this module, for use with
+/// `#[serde(with = "muhex::serde::prefixed")]`. Deserialization accepts hex
+/// with or without the prefix.
+pub mod prefixed {
+    use serde::Deserializer;
+
+    pub use super::serialize_prefixed as serialize;
+    use super::{FromBytes, deserialize_prefixed};
+    use crate::Prefix;
+
+    #[inline(always)]
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromBytes,
+    {
+        deserialize_prefixed(deserializer, Prefix::Optional)
+    }
+
+    /// Like the parent module, but deserialization rejects hex without the
+    /// prefix, for use with
+    /// `#[serde(with = "muhex::serde::prefixed::required")]`.
+    pub mod required {
+        use serde::Deserializer;
+
+        pub use super::serialize;
+        use crate::{
+            Prefix,
+            serde::{FromBytes, deserialize_prefixed},
+        };
+
+        #[inline(always)]
+        pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
         where
-            E: de::Error,
+            D: Deserializer<'de>,
+            T: FromBytes,
         {
-            decode(value).map_err(de::Error::custom)
+            deserialize_prefixed(deserializer, Prefix::Required)
         }
     }
-
-    let bytes = deserializer.deserialize_str(HexVisitor)?;
-    T::from_bytes(bytes).map_err(de::Error::custom)
 }
 
 #[cfg(test)]
@@ -77,6 +231,20 @@ mod tests {
         data: Vec<u8>,
     }
 
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestStructUpper {
+        #[serde(with = "crate::serde::upper")]
+        data: Vec<u8>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestStructPrefixed {
+        #[serde(with = "crate::serde::prefixed")]
+        optional: Vec<u8>,
+        #[serde(with = "crate::serde::prefixed::required")]
+        required: [u8; 4],
+    }
+
     #[derive(Debug, Serialize, Deserialize, PartialEq)]
     struct TestStructFixedWidth(#[serde(with = "crate::serde")] [u8; 32]);
 
@@ -98,6 +266,73 @@ mod tests {
         prop_assert_eq!(test_struct, deserialized);
     }
 
+    #[test_strategy::proptest]
+    fn test_serde_roundtrip_upper(data: Vec<u8>) {
+        let test_struct = TestStructUpper { data };
+        let serialized = serde_json::to_string(&test_struct).unwrap();
+        prop_assert_eq!(
+            &serialized,
+            &format!(
+                r#"{{"data":"{}"}}"#,
+                hex::encode_upper(&test_struct.data)
+            )
+        );
+        let deserialized: TestStructUpper =
+            serde_json::from_str(&serialized).unwrap();
+        prop_assert_eq!(test_struct, deserialized);
+    }
+
+    #[test_strategy::proptest]
+    fn test_serde_roundtrip_prefixed(optional: Vec<u8>, required: [u8; 4]) {
+        let test_struct = TestStructPrefixed { optional, required };
+        let serialized = serde_json::to_string(&test_struct).unwrap();
+        prop_assert_eq!(
+            &serialized,
+            &format!(
+                r#"{{"optional":"0x{}","required":"0x{}"}}"#,
+                hex::encode(&test_struct.optional),
+                hex::encode(test_struct.required)
+            )
+        );
+        let deserialized: TestStructPrefixed =
+            serde_json::from_str(&serialized).unwrap();
+        prop_assert_eq!(test_struct, deserialized);
+    }
+
+    #[test]
+    fn test_deserialize_prefix_policy() {
+        let deserialized: TestStructPrefixed = serde_json::from_str(
+            r#"{"optional":"abcd","required":"0XDEADBEEF"}"#,
+        )
+        .unwrap();
+        assert_eq!(deserialized.optional, [0xab, 0xcd]);
+        assert_eq!(deserialized.required, [0xde, 0xad, 0xbe, 0xef]);
+
+        let err = serde_json::from_str::<TestStructPrefixed>(
+            r#"{"optional":"0xabcd","required":"deadbeef"}"#,
+        )
+        .expect_err("err");
+        assert!(err.to_string().starts_with("missing hex prefix at index 0"));
+    }
+
+    #[test]
+    fn test_deserialize_fixed_width_wrong_length() {
+        let err = serde_json::from_str::<TestStructFixedWidth>(r#""abcd""#)
+            .expect_err("err");
+        assert_eq!(
+            err.to_string(),
+            "invalid length 2, expected a hex string of 32 bytes at line 1 \
+             column 6"
+        );
+
+        let err = serde_json::from_str::<TestStructFixedWidth>(r#""abc""#)
+            .expect_err("err");
+        assert!(
+            err.to_string()
+                .starts_with("hex string length must be even")
+        );
+    }
+
     #[test_strategy::proptest]
     fn test_serialize_parity(data: Vec<u8>) {
         let mut serializer = serde_json::Serializer::new(Vec::new());
@@ -126,4 +361,13 @@ mod tests {
 
         prop_assert_eq!(our_result, hex_result);
     }
+
+    #[test_strategy::proptest]
+    fn test_serialize_upper_parity(data: Vec<u8>) {
+        let mut ours = serde_json::Serializer::new(Vec::new());
+        let mut theirs = serde_json::Serializer::new(Vec::new());
+        super::serialize_upper(&data, &mut ours).unwrap();
+        hex::serde::serialize_upper(&data, &mut theirs).unwrap();
+        prop_assert_eq!(ours.into_inner(), theirs.into_inner());
+    }
 }
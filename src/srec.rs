@@ -0,0 +1,527 @@
+//! Motorola S-record files (S19, S28 and S37), as used to flash
+//! microcontrollers.
+//!
+//! ```
+//! let file = "\
+//! S107000001020304EE
+//! S5030001FB
+//! S9030000FC
+//! ";
+//! let image = muhex::srec::parse(file)?;
+//! assert_eq!(image.get(0x0002), Some(3));
+//! assert_eq!(muhex::srec::to_string(&image), file);
+//! # Ok::<(), muhex::srec::ParseError>(())
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str};
+
+pub use crate::firmware::{ParseError, ParseErrorKind};
+use crate::{
+    Buf,
+    Case,
+    Error,
+    HEX_ENCODE_LUT_UPPER,
+    decode_into,
+    encode_to_buf_with_lut,
+    image::MemoryImage,
+    parse::lines,
+};
+
+/// Most data bytes [`write()`] puts in a record, so that it fits with any
+/// address width.
+pub const MAX_DATA_LEN: usize = 250;
+
+/// Most bytes a record holds after its byte count.
+const MAX_RECORD_LEN: usize = 255;
+
+/// The width of the address field of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AddressWidth {
+    /// 16 bits, in `S1` data and `S9` start records, as in S19 files.
+    Bits16,
+    /// 24 bits, in `S2` data and `S8` start records, as in S28 files.
+    Bits24,
+    /// 32 bits, in `S3` data and `S7` start records, as in S37 files.
+    Bits32,
+}
+
+impl AddressWidth {
+    /// Returns the narrowest width that holds `address`.
+    pub const fn of(address: u32) -> Self {
+        match address {
+            0..=0xffff => AddressWidth::Bits16,
+            0x1_0000..=0xff_ffff => AddressWidth::Bits24,
+            _ => AddressWidth::Bits32,
+        }
+    }
+
+    const fn len(self) -> usize {
+        match self {
+            AddressWidth::Bits16 => 2,
+            AddressWidth::Bits24 => 3,
+            AddressWidth::Bits32 => 4,
+        }
+    }
+}
+
+/// A record of an S-record file, one line.
+///
+/// Records display as the line they are written as, and [`Record::parse`]
+/// reads one back:
+///
+/// ```
+/// use muhex::srec::{AddressWidth, Record};
+///
+/// let record = Record::Start {
+///     width: AddressWidth::Bits32,
+///     address: 0x0800_0000,
+/// };
+/// assert_eq!(record.to_string(), "S70508000000F2");
+/// assert_eq!(Record::parse("S70508000000F2")?, record);
+/// # Ok::<(), muhex::srec::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Record {
+    /// `S0`: free-form header data, such as a file name.
+    Header(Vec<u8>),
+    /// `S1`, `S2` or `S3`: bytes at `address`, which must fit in `width`.
+    Data {
+        width: AddressWidth,
+        address: u32,
+        data: Vec<u8>,
+    },
+    /// `S5` or `S6`: the number of data records before it, written as `S5`
+    /// if it fits in 16 bits and `S6` otherwise.
+    Count(u32),
+    /// `S7`, `S8` or `S9`: the address execution starts at, which ends the
+    /// file.
+    Start { width: AddressWidth, address: u32 },
+}
+
+impl Record {
+    /// Parses a single record, ignoring surrounding whitespace.
+    #[inline]
+    pub fn parse<T: AsRef<[u8]>>(line: T) -> Result<Self, ParseError> {
+        let mut buf = [0; MAX_RECORD_LEN + 1];
+        let (record_type, address, data) =
+            decode_record(line.as_ref().trim_ascii(), 0, &mut buf)?;
+        Ok(Self::from_fields(record_type, address, data))
+    }
+
+    /// Builds a record from fields [`decode_record`] has validated.
+    fn from_fields(record_type: u8, address: u32, data: &[u8]) -> Self {
+        match record_type {
+            0 => Record::Header(data.to_vec()),
+            1..=3 => Record::Data {
+                width: width(record_type),
+                address,
+                data: data.to_vec(),
+            },
+            5 | 6 => Record::Count(address),
+            _ => Record::Start {
+                width: width(record_type),
+                address,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    /// # Panics
+    ///
+    /// Panics if an address or count does not fit its width, or if the
+    /// record holds more than 255 bytes.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (record_type, address, data): (u8, u32, &[u8]) = match *self {
+            Record::Header(ref data) => (0, 0, data),
+            Record::Data {
+                width,
+                address,
+                ref data,
+            } => (width.len() as u8 - 1, address, data),
+            Record::Count(count) => match AddressWidth::of(count) {
+                AddressWidth::Bits16 => (5, count, &[]),
+                _ => (6, count, &[]),
+            },
+            Record::Start { width, address } => {
+                (11 - width.len() as u8, address, &[])
+            }
+        };
+
+        write_record(f, record_type, address, data)
+    }
+}
+
+/// Returns the width of the address field of `record_type`.
+const fn width(record_type: u8) -> AddressWidth {
+    match record_type {
+        2 | 6 | 8 => AddressWidth::Bits24,
+        3 | 7 => AddressWidth::Bits32,
+        _ => AddressWidth::Bits16,
+    }
+}
+
+/// Writes a record of `record_type` holding `data`, without a newline.
+fn write_record<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    record_type: u8,
+    address: u32,
+    data: &[u8],
+) -> fmt::Result {
+    let width = width(record_type).len();
+    assert!(
+        width == 4 || address >> (width * 8) == 0,
+        "address must fit in the address width of the record"
+    );
+    let len = 1 + width + data.len() + 1;
+    assert!(
+        len <= MAX_RECORD_LEN + 1,
+        "record must be at most 255 bytes"
+    );
+
+    let mut bytes = [0; MAX_RECORD_LEN + 1];
+    bytes[0] = (len - 1) as u8;
+    bytes[1..1 + width].copy_from_slice(&address.to_be_bytes()[4 - width..]);
+    bytes[1 + width..len - 1].copy_from_slice(data);
+    bytes[len - 1] = checksum(&bytes[..len - 1]);
+
+    let mut line = [0; 2 + (MAX_RECORD_LEN + 1) * 2];
+    line[..2].copy_from_slice(&[b'S', b'0' + record_type]);
+    let hex = &mut line[2..2 + len * 2];
+    encode_to_buf_with_lut(&bytes[..len], hex, &HEX_ENCODE_LUT_UPPER)
+        .expect("Len of the line is always correct");
+    // SAFETY: the record type and hex digits are ASCII
+    out.write_str(unsafe { str::from_utf8_unchecked(&line[..2 + len * 2]) })
+}
+
+/// Returns the ones' complement of the sum of `bytes`, which makes the sum
+/// of a record and its checksum `0xFF`.
+fn checksum(bytes: &[u8]) -> u8 {
+    !bytes.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+/// Decodes the record on the line at `index` into `buf`, verifying its
+/// type, length and checksum, and returns its type, address and data.
+fn decode_record<'a>(
+    line: &[u8],
+    index: usize,
+    buf: &'a mut [u8; MAX_RECORD_LEN + 1],
+) -> Result<(u8, u32, &'a [u8]), ParseError> {
+    let error = |column, kind| ParseError::new(index, column, kind);
+
+    let record_type = match line {
+        [b'S', digit @ b'0'..=b'9', ..] => digit - b'0',
+        [b'S', byte, ..] => {
+            let kind = ParseErrorKind::InvalidChar { byte: *byte };
+            return Err(error(1, kind));
+        }
+        _ => return Err(error(0, ParseErrorKind::MissingStartCode)),
+    };
+    if record_type == 4 {
+        let kind = ParseErrorKind::UnknownRecordType { record_type };
+        return Err(error(1, kind));
+    }
+
+    let hex = &line[2..];
+    let Some(bytes) = buf.get_mut(..hex.len() / 2) else {
+        return Err(error(0, ParseErrorKind::InvalidLength));
+    };
+    // SAFETY: `decode_into` only writes initialized bytes
+    decode_into(hex, unsafe { bytes.dst() }, Case::Any).map_err(
+        |err| match err {
+            Error::InvalidChar { byte, index } => {
+                error(2 + index, ParseErrorKind::InvalidChar { byte })
+            }
+            _ => error(0, ParseErrorKind::OddLength),
+        },
+    )?;
+
+    let bytes = &*bytes;
+    if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+        return Err(error(0, ParseErrorKind::InvalidLength));
+    }
+    let (&found, fields) =
+        bytes.split_last().expect("Records always hold a checksum");
+    let expected = checksum(fields);
+    if found != expected {
+        let kind = ParseErrorKind::ChecksumMismatch { expected, found };
+        return Err(error(line.len() - 2, kind));
+    }
+
+    // Only header and data records have data after their address
+    let width = width(record_type).len();
+    let Some((address, data)) = fields[1..].split_at_checked(width) else {
+        return Err(error(0, ParseErrorKind::MalformedRecord));
+    };
+    if !data.is_empty() && record_type > 3 {
+        return Err(error(0, ParseErrorKind::MalformedRecord));
+    }
+    let address = address
+        .iter()
+        .fold(0, |address, &byte| address << 8 | byte as u32);
+
+    Ok((record_type, address, data))
+}
+
+/// Iterates over the records of an S-record file, skipping blank lines.
+#[inline]
+pub fn records<T: AsRef<[u8]> + ?Sized>(
+    input: &T,
+) -> impl Iterator<Item = Result<Record, ParseError>> {
+    lines(input.as_ref()).map(|(index, line)| {
+        let mut buf = [0; MAX_RECORD_LEN + 1];
+        let (record_type, address, data) =
+            decode_record(line, index, &mut buf)?;
+        Ok(Record::from_fields(record_type, address, data))
+    })
+}
+
+/// Reads an S-record file into a [`MemoryImage`], up to its start record.
+///
+/// Header records are skipped, and count records are checked against the
+/// number of data records before them.
+#[inline]
+pub fn parse<T: AsRef<[u8]>>(input: T) -> Result<MemoryImage, ParseError> {
+    let mut image = MemoryImage::new();
+    let mut count = 0;
+    let mut end = 0;
+    let mut buf = [0; MAX_RECORD_LEN + 1];
+
+    for (index, line) in lines(input.as_ref()) {
+        let (record_type, address, data) =
+            decode_record(line, index, &mut buf)?;
+
+        match record_type {
+            0 => {}
+            1..=3 => {
+                if address as u64 + data.len() as u64 > 1 << 32 {
+                    let kind = ParseErrorKind::AddressOverflow;
+                    return Err(ParseError::new(index, 4, kind));
+                }
+                image.insert(address, data);
+                count += 1;
+            }
+            5 | 6 if address != count => {
+                let kind = ParseErrorKind::RecordCountMismatch {
+                    expected: count,
+                    found: address,
+                };
+                return Err(ParseError::new(index, 4, kind));
+            }
+            5 | 6 => {}
+            _ => {
+                image.set_entry_point(Some(address));
+                return Ok(image);
+            }
+        }
+        end = index + 1;
+    }
+
+    Err(ParseError::new(end, 0, ParseErrorKind::MissingEndOfFile))
+}
+
+/// Writes `image` as an S-record file to `out`, with up to `record_len`
+/// bytes per data record.
+///
+/// The address width is the narrowest that holds every address and the
+/// entry point. The data records are followed by a count record and a
+/// start record for the entry point, or for address 0 if there is none.
+///
+/// # Panics
+///
+/// Panics if `record_len` is 0 or above [`MAX_DATA_LEN`].
+pub fn write<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    image: &MemoryImage,
+    record_len: usize,
+) -> fmt::Result {
+    assert!(
+        (1..=MAX_DATA_LEN).contains(&record_len),
+        "record length must be between 1 and 250"
+    );
+
+    let last = image
+        .segments()
+        .map(|(start, bytes)| start + (bytes.len() as u32 - 1))
+        .last()
+        .unwrap_or(0);
+    let entry_point = image.entry_point().unwrap_or(0);
+    let width = AddressWidth::of(last.max(entry_point));
+    let mut count = 0;
+
+    for (start, bytes) in image.segments() {
+        for (i, data) in bytes.chunks(record_len).enumerate() {
+            let address = start + (i * record_len) as u32;
+            write_record(out, width.len() as u8 - 1, address, data)?;
+            out.write_char('\n')?;
+            count += 1;
+        }
+    }
+
+    if count <= 0xff_ffff {
+        writeln!(out, "{}", Record::Count(count))?;
+    }
+    let start = Record::Start {
+        width,
+        address: entry_point,
+    };
+    writeln!(out, "{start}")
+}
+
+/// Returns `image` as an S-record file with 16 bytes per data record, see
+/// [`write()`].
+#[inline]
+pub fn to_string(image: &MemoryImage) -> String {
+    let mut out = String::new();
+    write(&mut out, image, 16).expect("Writing to a String never fails");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::{
+        AddressWidth,
+        ParseErrorKind,
+        Record,
+        parse,
+        records,
+        to_string,
+        write,
+    };
+    use crate::{image::MemoryImage, parse::tests::error};
+
+    /// Writes at addresses of a random width, including the very end.
+    fn memory_image() -> impl Strategy<Value = MemoryImage> {
+        let address = prop_oneof![
+            any::<u16>().prop_map(u32::from),
+            (0u32..0x100_0000),
+            any::<u32>(),
+            (0u32..0x200).prop_map(|address| u32::MAX - address),
+        ];
+        let writes = vec((address, vec(any::<u8>(), 0..300)), 0..10);
+        (writes, any::<u32>()).prop_map(|(writes, entry_point)| {
+            let mut image = MemoryImage::new();
+            for (address, mut data) in writes {
+                data.truncate((u32::MAX - address) as usize + 1);
+                image.insert(address, &data);
+            }
+            image.set_entry_point(Some(entry_point));
+            image
+        })
+    }
+
+    #[test_strategy::proptest(cases = 300)]
+    fn test_srec_roundtrip(
+        #[strategy(memory_image())] image: MemoryImage,
+        #[strategy(1usize..=250)] record_len: usize,
+    ) {
+        prop_assert_eq!(&parse(to_string(&image))?, &image);
+
+        let mut file = String::new();
+        write(&mut file, &image, record_len)?;
+        prop_assert_eq!(&parse(&file)?, &image);
+        for record in records(&file) {
+            let record = record?;
+            prop_assert_eq!(&Record::parse(record.to_string())?, &record);
+        }
+    }
+
+    #[test]
+    fn test_srec_example() {
+        // The example from the srec(5) man page
+        let file = "\
+S00600004844521B
+S1130000285F245F2212226A000424290008237C2A
+S11300100002000800082629001853812341001813
+S113002041E900084E42234300182342000824A952
+S107003000144ED492
+S5030004F8
+S9030000FC
+";
+        let image = parse(file).unwrap();
+        assert_eq!(image.segments().count(), 1);
+        assert_eq!(image.len(), 0x34);
+        assert_eq!(image.get(0x33), Some(0xd4));
+        assert_eq!(image.entry_point(), Some(0));
+
+        let records: Vec<_> = records(file).map(Result::unwrap).collect();
+        assert_eq!(records[0], Record::Header(b"HDR".to_vec()));
+        assert_eq!(records[5], Record::Count(4));
+        assert_eq!(
+            records[6],
+            Record::Start {
+                width: AddressWidth::Bits16,
+                address: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_srec_errors() {
+        assert_eq!(
+            parse("S10400004200\nS9030000FC\n"),
+            Err(error(
+                1,
+                11,
+                ParseErrorKind::ChecksumMismatch {
+                    expected: 0xb9,
+                    found: 0x00
+                }
+            ))
+        );
+        assert_eq!(
+            parse("S1040000x2B7\n"),
+            Err(error(1, 9, ParseErrorKind::InvalidChar { byte: b'x' }))
+        );
+        assert_eq!(
+            parse("S1040000422B7\n"),
+            Err(error(1, 1, ParseErrorKind::OddLength))
+        );
+        assert_eq!(
+            parse("S104000042B9\n"),
+            Err(error(2, 1, ParseErrorKind::MissingEndOfFile))
+        );
+        assert_eq!(
+            parse(":104000042B7\n"),
+            Err(error(1, 1, ParseErrorKind::MissingStartCode))
+        );
+        assert_eq!(
+            parse("S4030000FC\n"),
+            Err(error(
+                1,
+                2,
+                ParseErrorKind::UnknownRecordType { record_type: 4 }
+            ))
+        );
+        assert_eq!(
+            parse("S1050000422B7\n"),
+            Err(error(1, 1, ParseErrorKind::OddLength))
+        );
+        assert_eq!(
+            parse("S10600004200B7\n"),
+            Err(error(1, 1, ParseErrorKind::InvalidLength))
+        );
+        assert_eq!(
+            parse("S9040000FFFC\n"),
+            Err(error(1, 1, ParseErrorKind::MalformedRecord))
+        );
+        assert_eq!(
+            parse("S104000042B9\nS5030002FA\nS9030000FC\n"),
+            Err(error(
+                2,
+                5,
+                ParseErrorKind::RecordCountMismatch {
+                    expected: 1,
+                    found: 2
+                }
+            ))
+        );
+        assert_eq!(
+            parse("S307FFFFFFFF0102F9\nS70500000000FA\n"),
+            Err(error(1, 5, ParseErrorKind::AddressOverflow))
+        );
+    }
+}
@@ -0,0 +1,319 @@
+// SWAR (SIMD within a register) backend, used on targets without x86_64
+// intrinsics when the nightly `portable-simd` feature is disabled. Every
+// step works on plain `u64` words, so it builds on stable Rust.
+
+use core::mem::MaybeUninit;
+
+use crate::{
+    Case,
+    Error,
+    IgnoreSet,
+    compact_scalar,
+    decode_remainder_lut,
+    encode_grouped_with,
+    encode_scalar,
+    invalid_hex_char_error,
+};
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+const LOW_NIBBLES: u64 = 0x000F_000F_000F_000F;
+const LOW_BYTES: u64 = 0x00FF_00FF_00FF_00FF;
+
+/// Spreads 4 bytes into the even bytes of a little-endian `u64`.
+#[inline(always)]
+fn spread(x: u32) -> u64 {
+    let x = x as u64;
+    let x = (x | (x << 16)) & 0x0000_FFFF_0000_FFFF;
+    (x | (x << 8)) & LOW_BYTES
+}
+
+/// Gathers the even bytes of a little-endian `u64` into 4 bytes, the
+/// inverse of [`spread`].
+#[inline(always)]
+fn gather(x: u64) -> u32 {
+    let x = x & LOW_BYTES;
+    let x = (x | (x >> 8)) & 0x0000_FFFF_0000_FFFF;
+    (x | (x >> 16)) as u32
+}
+
+/// Encodes 4 bytes into 8 hex chars, taking the case of the letters from
+/// `lut`.
+#[inline(always)]
+fn encode_word(x: u32, lut: &[u8; 16]) -> u64 {
+    let spread = spread(x);
+
+    // Little-endian: the high nibble goes first, into the even byte
+    let hi = (spread >> 4) & LOW_NIBBLES;
+    let lo = (spread & LOW_NIBBLES) << 8;
+    let nibbles = hi | lo;
+
+    // Bit 4 of `nibble + 6` is set exactly for nibbles above 9, which
+    // need to skip from '9' + 1 to 'a' (or 'A')
+    let above_9 = ((nibbles + 6 * ONES) >> 4) & ONES;
+    nibbles + (b'0' as u64) * ONES + above_9 * (lut[10] - b'0' - 10) as u64
+}
+
+/// Sets the high bit of every byte of `x` in `lo..=hi`. Bytes of `x` must
+/// be ASCII.
+#[inline(always)]
+fn in_range(x: u64, lo: u8, hi: u8) -> u64 {
+    let ge_lo = x + (0x80 - lo as u64) * ONES;
+    let gt_hi = x + (0x7F - hi as u64) * ONES;
+    ge_lo & !gt_hi & HIGH_BITS
+}
+
+/// Decodes 8 hex chars into 4 bytes, or returns `None` if any of them is
+/// not a hex digit in `case`.
+#[inline(always)]
+fn decode_word(x: u64, case: Case) -> Option<u32> {
+    if x & HIGH_BITS != 0 {
+        return None;
+    }
+
+    let digits = in_range(x, b'0', b'9');
+    let letters = match case {
+        Case::Lower => in_range(x, b'a', b'f'),
+        Case::Upper => in_range(x, b'A', b'F'),
+        Case::Any => in_range(x | (0x20 * ONES), b'a', b'f'),
+    };
+    if digits | letters != HIGH_BITS {
+        return None;
+    }
+
+    // The low nibble of 'a'-'f' and 'A'-'F' is 1-6, so letters are 9 off
+    let nibbles = (x & (0x0F * ONES)) + (letters >> 7) * 9;
+    let bytes = ((nibbles & LOW_BYTES) << 4) | ((nibbles >> 8) & LOW_BYTES);
+    Some(gather(bytes))
+}
+
+#[inline]
+pub(crate) fn encode(data: &[u8], dst: &mut [MaybeUninit<u8>], lut: &[u8; 16]) {
+    let (chunks, remainder) = data.as_chunks::<4>();
+    let (out, _) = dst.as_chunks_mut::<8>();
+
+    for (chunk, out) in chunks.iter().zip(out) {
+        let hex = encode_word(u32::from_le_bytes(*chunk), lut).to_le_bytes();
+        for (dst, src) in out.iter_mut().zip(hex) {
+            dst.write(src);
+        }
+    }
+
+    let pos = data.len() - remainder.len();
+    encode_scalar(remainder, &mut dst[pos * 2..], lut);
+}
+
+/// Without byte shuffles, separators are written between the groups as
+/// they are encoded.
+#[inline]
+pub(crate) fn encode_grouped(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+    group: usize,
+    separator: u8,
+) {
+    encode_grouped_with(data, dst, lut, group, separator, encode);
+}
+
+/// Copies the bytes of `input` not in `ignore` to `output`, copying words
+/// of plain hex digits whole unless `ignore` contains some digits.
+#[inline]
+pub(crate) fn compact(
+    input: &[u8],
+    output: &mut [u8],
+    ignore: &IgnoreSet,
+) -> usize {
+    let (chunks, remainder) = input.as_chunks::<8>();
+    let keeps_digits = !b"0123456789abcdefABCDEF"
+        .iter()
+        .any(|&byte| ignore.contains(byte));
+    let mut len = 0;
+
+    for chunk in chunks {
+        let word = u64::from_le_bytes(*chunk);
+        if keeps_digits && decode_word(word, Case::Any).is_some() {
+            output[len..len + 8].copy_from_slice(chunk);
+            len += 8;
+        } else {
+            len += compact_scalar(chunk, &mut output[len..], ignore);
+        }
+    }
+
+    len + compact_scalar(remainder, &mut output[len..], ignore)
+}
+
+#[inline]
+pub(crate) fn decode(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    let (chunks, remainder) = input.as_chunks::<8>();
+    let (out, _) = output.as_chunks_mut::<4>();
+
+    for (i, (chunk, out)) in chunks.iter().zip(out).enumerate() {
+        let bytes = decode_word(u64::from_le_bytes(*chunk), case)
+            .ok_or_else(|| invalid_hex_char_error(input, i * 8, case))?
+            .to_le_bytes();
+        for (dst, src) in out.iter_mut().zip(bytes) {
+            dst.write(src);
+        }
+    }
+
+    let pos = input.len() - remainder.len();
+    decode_remainder_lut(input, output, pos, pos / 2, input.len() - pos, case)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::{
+        Buf,
+        Case,
+        Error,
+        HEX_ENCODE_LUT,
+        HEX_ENCODE_LUT_UPPER,
+        IgnoreSet,
+        decode_remainder_lut,
+        encode_grouped_reference,
+    };
+
+    fn hex_chars() -> impl Strategy<Value = Vec<u8>> {
+        let chars = b"0123456789abcdefABCDEF".to_vec();
+        proptest::collection::vec(proptest::sample::select(chars), 0..100)
+            .prop_map(|mut v| {
+                v.truncate(v.len() & !1);
+                v
+            })
+    }
+
+    fn decode_reference(input: &[u8], case: Case) -> Result<Vec<u8>, Error> {
+        let mut output = vec![0u8; input.len() / 2];
+        let dst = unsafe { output.dst() };
+        decode_remainder_lut(input, dst, 0, 0, input.len(), case)?;
+        Ok(output)
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_swar_encode_parity(input: Vec<u8>) {
+        let mut output = vec![0u8; input.len() * 2];
+        super::encode(&input, unsafe { output.dst() }, &HEX_ENCODE_LUT);
+        prop_assert_eq!(String::from_utf8(output).unwrap(), hex::encode(input));
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_swar_encode_upper_parity(input: Vec<u8>) {
+        let mut output = vec![0u8; input.len() * 2];
+        super::encode(&input, unsafe { output.dst() }, &HEX_ENCODE_LUT_UPPER);
+        prop_assert_eq!(
+            String::from_utf8(output).unwrap(),
+            hex::encode_upper(input)
+        );
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_swar_encode_grouped_parity(
+        input: Vec<u8>,
+        #[strategy(1usize..20)] group: usize,
+        separator: u8,
+    ) {
+        let expected =
+            encode_grouped_reference(&input, &HEX_ENCODE_LUT, group, separator);
+        let mut output = vec![0u8; expected.len()];
+        let dst = unsafe { output.dst() };
+        super::encode_grouped(&input, dst, &HEX_ENCODE_LUT, group, separator);
+        prop_assert_eq!(output, expected);
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_swar_compact(
+        #[strategy(proptest::collection::vec(
+            proptest::sample::select(b"0123456789abcdef :-\n\xff".to_vec()),
+            0..100,
+        ))]
+        input: Vec<u8>,
+        ignore_digits: bool,
+    ) {
+        let mut ignore = IgnoreSet::default();
+        if ignore_digits {
+            ignore = ignore.with(b'0');
+        }
+        let expected: Vec<u8> = input
+            .iter()
+            .copied()
+            .filter(|&byte| !ignore.contains(byte))
+            .collect();
+
+        let mut output = vec![0u8; input.len()];
+        let len = super::compact(&input, &mut output, &ignore);
+        prop_assert_eq!(&output[..len], &expected[..]);
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_swar_decode_parity(input: Vec<u8>) {
+        let input = &input[..input.len() & !1];
+        let mut output = vec![0u8; input.len() / 2];
+        let result = super::decode(input, unsafe { output.dst() }, Case::Any);
+        prop_assert_eq!(result.map(|_| output).ok(), hex::decode(input).ok());
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_swar_roundtrip(input: Vec<u8>) {
+        let encoded = hex::encode_upper(&input);
+        let mut output = vec![0u8; input.len()];
+        super::decode(encoded.as_bytes(), unsafe { output.dst() }, Case::Any)?;
+        prop_assert_eq!(output, input);
+    }
+
+    #[test_strategy::proptest(cases = 10000)]
+    fn test_swar_strict_case(#[strategy(hex_chars())] input: Vec<u8>) {
+        for case in [Case::Lower, Case::Upper, Case::Any] {
+            let mut output = vec![0u8; input.len() / 2];
+            let result = super::decode(&input, unsafe { output.dst() }, case);
+            prop_assert_eq!(
+                result.map(|_| output),
+                decode_reference(&input, case),
+                "case {:?}",
+                case
+            );
+        }
+    }
+
+    #[test]
+    fn test_swar_reports_invalid_char_index() {
+        for at in 0..40 {
+            let mut input = *b"0123456789abcdefABCDEF0123456789abcdefAB";
+            input[at] = b'g';
+
+            let mut output = [0u8; 20];
+            assert_eq!(
+                super::decode(&input, unsafe { output.dst() }, Case::Any),
+                Err(Error::InvalidChar {
+                    byte: b'g',
+                    index: at
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_swar_rejects_every_invalid_byte() {
+        for case in [Case::Lower, Case::Upper, Case::Any] {
+            let lut = case.decode_lut();
+            for ch in (0..=255u8).filter(|&b| lut[b as usize] == 255) {
+                for at in 0..8 {
+                    let mut input = match case {
+                        Case::Lower => *b"0123abcd",
+                        Case::Upper => *b"0123ABCD",
+                        Case::Any => *b"0123abCD",
+                    };
+                    input[at] = ch;
+                    let word = u64::from_le_bytes(input);
+                    assert_eq!(super::decode_word(word, case), None);
+                }
+            }
+        }
+    }
+}
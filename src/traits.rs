@@ -0,0 +1,154 @@
+// Drop-in equivalents of the `hex` crate's `ToHex` and `FromHex` traits.
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use crate::{Error, decode_array};
+#[cfg(feature = "alloc")]
+use crate::{decode, encode, encode_upper};
+
+/// Encodes values as hex strings, like `hex::ToHex`.
+///
+/// Implemented for everything that is `AsRef<[u8]>`, such as `[u8]`,
+/// `Vec<u8>`, `[u8; N]` and `Box<[u8]>`. The output can be any type that
+/// converts from a `String`, e.g. `String`, `Cow<str>` or `Box<str>`.
+///
+/// ```
+/// use muhex::ToHex;
+///
+/// assert_eq!(b"muhex".encode_hex::<String>(), "6d75686578");
+/// assert_eq!([0xab, 0xcd].encode_hex_upper::<String>(), "ABCD");
+/// ```
+#[cfg(feature = "alloc")]
+pub trait ToHex {
+    /// Encodes `self` using lowercase hex digits.
+    fn encode_hex<T: From<String>>(&self) -> T;
+
+    /// Encodes `self` using uppercase hex digits.
+    fn encode_hex_upper<T: From<String>>(&self) -> T;
+}
+
+#[cfg(feature = "alloc")]
+impl<T: AsRef<[u8]> + ?Sized> ToHex for T {
+    #[inline]
+    fn encode_hex<U: From<String>>(&self) -> U {
+        U::from(encode(self))
+    }
+
+    #[inline]
+    fn encode_hex_upper<U: From<String>>(&self) -> U {
+        U::from(encode_upper(self))
+    }
+}
+
+/// Decodes values from hex strings, like `hex::FromHex`.
+///
+/// Both letter cases are accepted, as with [`decode`](crate::decode).
+///
+/// ```
+/// use muhex::FromHex;
+///
+/// assert_eq!(<[u8; 2]>::from_hex("abCD")?, [0xab, 0xcd]);
+/// # #[cfg(feature = "alloc")]
+/// assert_eq!(Vec::from_hex(b"6d75686578")?, b"muhex");
+/// # Ok::<(), muhex::Error>(())
+/// ```
+pub trait FromHex: Sized {
+    type Error;
+
+    /// Decodes `hex` into a value of this type.
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error>;
+}
+
+#[cfg(feature = "alloc")]
+impl FromHex for Vec<u8> {
+    type Error = Error;
+
+    #[inline]
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error> {
+        decode(hex)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FromHex for Box<[u8]> {
+    type Error = Error;
+
+    #[inline]
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error> {
+        Vec::from_hex(hex).map(Vec::into_boxed_slice)
+    }
+}
+
+impl<const N: usize> FromHex for [u8; N] {
+    type Error = Error;
+
+    #[inline]
+    fn from_hex<T: AsRef<[u8]>>(hex: T) -> Result<Self, Self::Error> {
+        decode_array(hex)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use std::borrow::Cow;
+
+    use proptest::prelude::*;
+
+    use super::{FromHex, ToHex};
+    use crate::Error;
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_to_hex_parity(input: Vec<u8>) {
+        prop_assert_eq!(
+            input.encode_hex::<String>(),
+            hex::ToHex::encode_hex::<String>(&input)
+        );
+        prop_assert_eq!(
+            input.as_slice().encode_hex_upper::<String>(),
+            hex::ToHex::encode_hex_upper::<String>(&input)
+        );
+
+        let boxed = input.clone().into_boxed_slice();
+        let cow: Cow<str> = boxed.encode_hex();
+        prop_assert_eq!(cow, hex::encode(&input));
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_from_hex_parity(input: String) {
+        prop_assert_eq!(
+            Vec::from_hex(&input).ok(),
+            <Vec<u8> as hex::FromHex>::from_hex(&input).ok()
+        );
+        prop_assert_eq!(
+            <Box<[u8]>>::from_hex(&input).ok(),
+            <Vec<u8> as hex::FromHex>::from_hex(&input)
+                .ok()
+                .map(Vec::into_boxed_slice)
+        );
+        prop_assert_eq!(
+            <[u8; 4]>::from_hex(&input).ok(),
+            <[u8; 4] as hex::FromHex>::from_hex(&input).ok()
+        );
+    }
+
+    #[test_strategy::proptest(cases = 1000)]
+    fn test_from_hex_array_roundtrip(input: [u8; 32]) {
+        prop_assert_eq!(
+            <[u8; 32]>::from_hex(input.encode_hex::<String>())?,
+            input
+        );
+    }
+
+    #[test]
+    fn test_from_hex_array_wrong_length() {
+        assert_eq!(
+            <[u8; 4]>::from_hex("abcdef"),
+            Err(Error::InvalidOutputLength {
+                expected: 3,
+                actual: 4
+            })
+        );
+        assert_eq!(<[u8; 4]>::from_hex("abcdef0"), Err(Error::OddLength));
+    }
+}
@@ -0,0 +1,1650 @@
+// x86_64 backend: SSE2 to AVX-512 VBMI kernels, picked at runtime.
+
+use core::{
+    arch::x86_64::*,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+use crate::{
+    COMPACT_SHUFFLES,
+    Case,
+    Error,
+    GroupShuffle,
+    IgnoreSet,
+    compact_scalar,
+    decode_remainder_lut,
+    encode_grouped_with,
+    encode_scalar,
+    invalid_hex_char_error,
+};
+
+// ─── Encode: PSHUFB-based fast paths ────────────────────────────────────
+
+#[inline(always)]
+unsafe fn encode_simd_64(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    lut: &[u8; 16],
+) {
+    // Process 64 input bytes → 128 output bytes using AVX-512
+    unsafe {
+        let raw = _mm512_loadu_si512(input.cast());
+        let mask = _mm512_set1_epi8(0x0F);
+        let lut = _mm512_broadcast_i32x4(_mm_loadu_si128(lut.as_ptr().cast()));
+
+        // Extract nibbles
+        let hi = _mm512_and_si512(_mm512_srli_epi16(raw, 4), mask);
+        let lo = _mm512_and_si512(raw, mask);
+
+        // LUT lookup: single vpshufb per nibble set
+        let hi_ascii = _mm512_shuffle_epi8(lut, hi);
+        let lo_ascii = _mm512_shuffle_epi8(lut, lo);
+
+        // Interleave hi[i], lo[i] → output[2i], output[2i+1]
+        // using vpermi2b (AVX-512 VBMI cross-lane byte permute)
+        //
+        // First 64 output bytes: hi[0],lo[0],hi[1],lo[1],...,hi[31],lo[31]
+        // Second 64 output bytes: hi[32],lo[32],...,hi[63],lo[63]
+        //
+        // vpermi2b uses 7-bit indices: bit 6 selects source (0=first, 1=second)
+        let perm_lo = _mm512_set_epi8(
+            // Bytes 63..0 (set_epi8 is high-to-low)
+            95, 31, 94, 30, 93, 29, 92, 28, 91, 27, 90, 26, 89, 25, 88, 24, 87,
+            23, 86, 22, 85, 21, 84, 20, 83, 19, 82, 18, 81, 17, 80, 16, 79, 15,
+            78, 14, 77, 13, 76, 12, 75, 11, 74, 10, 73, 9, 72, 8, 71, 7, 70, 6,
+            69, 5, 68, 4, 67, 3, 66, 2, 65, 1, 64, 0,
+        );
+        let perm_hi = _mm512_set_epi8(
+            127, 63, 126, 62, 125, 61, 124, 60, 123, 59, 122, 58, 121, 57, 120,
+            56, 119, 55, 118, 54, 117, 53, 116, 52, 115, 51, 114, 50, 113, 49,
+            112, 48, 111, 47, 110, 46, 109, 45, 108, 44, 107, 43, 106, 42, 105,
+            41, 104, 40, 103, 39, 102, 38, 101, 37, 100, 36, 99, 35, 98, 34,
+            97, 33, 96, 32,
+        );
+
+        let mut out_lo = perm_lo;
+        let mut out_hi = perm_hi;
+        out_lo = _mm512_permutex2var_epi8(hi_ascii, out_lo, lo_ascii);
+        out_hi = _mm512_permutex2var_epi8(hi_ascii, out_hi, lo_ascii);
+
+        _mm512_storeu_si512(output.cast(), out_lo);
+        _mm512_storeu_si512(output.add(64).cast(), out_hi);
+    }
+}
+
+#[inline(always)]
+unsafe fn encode_simd_32(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    lut: &[u8; 16],
+) {
+    unsafe {
+        let raw = _mm256_loadu_si256(input.cast());
+        let mask = _mm256_set1_epi8(0x0F);
+        let lut =
+            _mm256_broadcastsi128_si256(_mm_loadu_si128(lut.as_ptr().cast()));
+
+        // Extract nibbles
+        let hi = _mm256_and_si256(_mm256_srli_epi16(raw, 4), mask);
+        let lo = _mm256_and_si256(raw, mask);
+
+        // LUT lookup: single vpshufb per nibble set
+        let hi_ascii = _mm256_shuffle_epi8(lut, hi);
+        let lo_ascii = _mm256_shuffle_epi8(lut, lo);
+
+        // Interleave using unpack + lane fix
+        let interleaved_lo = _mm256_unpacklo_epi8(hi_ascii, lo_ascii);
+        let interleaved_hi = _mm256_unpackhi_epi8(hi_ascii, lo_ascii);
+        let final_lo =
+            _mm256_permute2x128_si256(interleaved_lo, interleaved_hi, 0x20);
+        let final_hi =
+            _mm256_permute2x128_si256(interleaved_lo, interleaved_hi, 0x31);
+
+        _mm256_storeu_si256(output.cast(), final_lo);
+        _mm256_storeu_si256(output.add(32).cast(), final_hi);
+    }
+}
+
+#[inline(always)]
+unsafe fn encode_simd_16(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    lut: &[u8; 16],
+) {
+    unsafe {
+        let raw = _mm_loadu_si128(input.cast());
+        let mask = _mm_set1_epi8(0x0F);
+        let lut = _mm_loadu_si128(lut.as_ptr().cast());
+
+        let hi = _mm_and_si128(_mm_srli_epi16(raw, 4), mask);
+        let lo = _mm_and_si128(raw, mask);
+
+        let hi_ascii = _mm_shuffle_epi8(lut, hi);
+        let lo_ascii = _mm_shuffle_epi8(lut, lo);
+
+        let interleaved_lo = _mm_unpacklo_epi8(hi_ascii, lo_ascii);
+        let interleaved_hi = _mm_unpackhi_epi8(hi_ascii, lo_ascii);
+
+        _mm_storeu_si128(output.cast(), interleaved_lo);
+        _mm_storeu_si128(output.add(16).cast(), interleaved_hi);
+    }
+}
+
+/// SSE2-only variant of `encode_simd_16`: without `pshufb` the nibbles are
+/// mapped to ASCII arithmetically, adding `'a' - '0' - 10` (or the same
+/// offset to `'A'`, following `lut`) to those above 9.
+#[inline(always)]
+unsafe fn encode_sse2_16(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    lut: &[u8; 16],
+) {
+    unsafe {
+        let raw = _mm_loadu_si128(input.cast());
+        let mask = _mm_set1_epi8(0x0F);
+        let bias_0 = _mm_set1_epi8(b'0' as i8);
+        let bias_a = _mm_set1_epi8((lut[10] - b'0' - 10) as i8);
+        let cmp_9 = _mm_set1_epi8(9);
+
+        let hi = _mm_and_si128(_mm_srli_epi16(raw, 4), mask);
+        let lo = _mm_and_si128(raw, mask);
+
+        let hi_ascii = _mm_add_epi8(
+            _mm_add_epi8(hi, bias_0),
+            _mm_and_si128(_mm_cmpgt_epi8(hi, cmp_9), bias_a),
+        );
+        let lo_ascii = _mm_add_epi8(
+            _mm_add_epi8(lo, bias_0),
+            _mm_and_si128(_mm_cmpgt_epi8(lo, cmp_9), bias_a),
+        );
+
+        let interleaved_lo = _mm_unpacklo_epi8(hi_ascii, lo_ascii);
+        let interleaved_hi = _mm_unpackhi_epi8(hi_ascii, lo_ascii);
+
+        _mm_storeu_si128(output.cast(), interleaved_lo);
+        _mm_storeu_si128(output.add(16).cast(), interleaved_hi);
+    }
+}
+
+/// Encodes 16 bytes like `encode_simd_16`, then spreads the digits out with
+/// `shuffle` to insert `separator` after every group, storing 48 bytes of
+/// which the first `shuffle.len` are meaningful.
+#[inline(always)]
+unsafe fn encode_grouped_simd_16(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    lut: &[u8; 16],
+    shuffle: &GroupShuffle,
+    separator: __m128i,
+) {
+    unsafe {
+        let raw = _mm_loadu_si128(input.cast());
+        let mask = _mm_set1_epi8(0x0F);
+        let lut = _mm_loadu_si128(lut.as_ptr().cast());
+
+        let hi = _mm_and_si128(_mm_srli_epi16(raw, 4), mask);
+        let lo = _mm_and_si128(raw, mask);
+
+        let hi_ascii = _mm_shuffle_epi8(lut, hi);
+        let lo_ascii = _mm_shuffle_epi8(lut, lo);
+
+        let interleaved_lo = _mm_unpacklo_epi8(hi_ascii, lo_ascii);
+        let interleaved_hi = _mm_unpackhi_epi8(hi_ascii, lo_ascii);
+
+        for i in 0..3 {
+            let from_lo = _mm_shuffle_epi8(
+                interleaved_lo,
+                _mm_loadu_si128(shuffle.lo[i].as_ptr().cast()),
+            );
+            let from_hi = _mm_shuffle_epi8(
+                interleaved_hi,
+                _mm_loadu_si128(shuffle.hi[i].as_ptr().cast()),
+            );
+            let separators = _mm_and_si128(
+                _mm_loadu_si128(shuffle.sep[i].as_ptr().cast()),
+                separator,
+            );
+
+            _mm_storeu_si128(
+                output.add(i * 16).cast(),
+                _mm_or_si128(_mm_or_si128(from_lo, from_hi), separators),
+            );
+        }
+    }
+}
+
+// ─── Compaction: dropping ignored bytes ─────────────────────────────────
+
+/// Copies the bytes of a 16-byte block not in `ignore` to `output`,
+/// returning how many there were. Stores 16 bytes, of which only that many
+/// are meaningful.
+///
+/// Membership is looked up like the decode kernels classify digits:
+/// `rows[lo]` holds a bit per high nibble, and high nibbles 8-15 select no
+/// bit at all, so non-ASCII bytes are always kept.
+#[inline(always)]
+unsafe fn compact_ssse3_16(
+    input: *const u8,
+    output: *mut u8,
+    rows: __m128i,
+) -> usize {
+    unsafe {
+        let raw = _mm_loadu_si128(input.cast());
+        let mask = _mm_set1_epi8(0x0F);
+        let bits =
+            _mm_setr_epi8(1, 2, 4, 8, 16, 32, 64, -128, 0, 0, 0, 0, 0, 0, 0, 0);
+
+        let hi = _mm_and_si128(_mm_srli_epi16(raw, 4), mask);
+        let lo = _mm_and_si128(raw, mask);
+        let ignored = _mm_and_si128(
+            _mm_shuffle_epi8(rows, lo),
+            _mm_shuffle_epi8(bits, hi),
+        );
+        let keep =
+            _mm_movemask_epi8(_mm_cmpeq_epi8(ignored, _mm_setzero_si128()))
+                as usize;
+
+        if keep == 0xFFFF {
+            _mm_storeu_si128(output.cast(), raw);
+            return 16;
+        }
+
+        let (keep_lo, keep_hi) = (keep & 0xFF, keep >> 8);
+        let shuffle_lo =
+            _mm_loadl_epi64(COMPACT_SHUFFLES[keep_lo].as_ptr().cast());
+        let shuffle_hi = _mm_add_epi8(
+            _mm_loadl_epi64(COMPACT_SHUFFLES[keep_hi].as_ptr().cast()),
+            _mm_set1_epi8(8),
+        );
+        let len_lo = keep_lo.count_ones() as usize;
+
+        _mm_storel_epi64(output.cast(), _mm_shuffle_epi8(raw, shuffle_lo));
+        _mm_storel_epi64(
+            output.add(len_lo).cast(),
+            _mm_shuffle_epi8(raw, shuffle_hi),
+        );
+
+        len_lo + keep_hi.count_ones() as usize
+    }
+}
+
+// ─── Decode: vpermi2b-based fast paths ──────────────────────────────────
+
+// 128-byte LUT for vpermi2b hex decode.
+// vpermi2b uses 7-bit indices: bits[5:0] = offset within 64-byte source,
+// bit[6] selects source (0 = LUT_LO, 1 = LUT_HI).
+//
+// '0'-'9' (0x30-0x39): bit6=0, indices 0x30-0x39 in LUT_LO → values 0-9
+// 'A'-'F' (0x41-0x46): bit6=1, indices 0x01-0x06 in LUT_HI → values 10-15
+// 'a'-'f' (0x61-0x66): bit6=1, indices 0x21-0x26 in LUT_HI → values 10-15
+// Everything else → 0x80 (invalid sentinel, bit 7 set)
+//
+// LUT_HI depends on the accepted letter case and lives in `DecodeTables`.
+// Bytes >= 0x80 alias the entries above and are rejected by the kernels.
+#[repr(align(64))]
+struct Aligned64([u8; 64]);
+
+static HEX_DECODE_VPERMI2B_LO: Aligned64 = Aligned64([
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x00..0x07
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x08..0x0F
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x10..0x17
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x18..0x1F
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x20..0x27
+    0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, // 0x28..0x2F
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, // 0x30..0x37 '0'..'7'
+    0x08, 0x09, 0x80, 0x80, 0x80, 0x80, 0x80,
+    0x80, // 0x38..0x3F '8','9',invalid
+]);
+
+/// Case-dependent validation tables shared by the decode kernels.
+#[repr(C, align(64))]
+struct DecodeTables {
+    /// Upper half of the vpermi2b LUT, holding the letters.
+    vpermi2b_hi: [u8; 64],
+    /// Smallest valid low nibble per high nibble class for the pshufb
+    /// kernels, `0xFF` for invalid classes.
+    minlo: [u8; 16],
+    /// Largest valid low nibble per high nibble class for the pshufb
+    /// kernels, `0` for invalid classes.
+    maxlo: [u8; 16],
+    /// Bits ORed into the input before the SSE2 letter range check, `0x20`
+    /// to fold letters to lowercase.
+    fold: u8,
+    /// First valid letter after folding for the SSE2 kernel.
+    first_letter: u8,
+}
+
+impl DecodeTables {
+    const fn new(case: Case) -> Self {
+        let mut tables = DecodeTables {
+            vpermi2b_hi: [0x80; 64],
+            minlo: [0xFF; 16],
+            maxlo: [0; 16],
+            fold: 0,
+            first_letter: b'a',
+        };
+
+        // '0'-'9' have high nibble 3 and low nibbles 0-9
+        tables.minlo[3] = 0;
+        tables.maxlo[3] = 9;
+
+        // 'A'-'F' and 'a'-'f' have high nibbles 4 and 6, low nibbles 1-6
+        let mut i = 1;
+        while i <= 6 {
+            if case.accepts_upper() {
+                tables.vpermi2b_hi[i] = 9 + i as u8;
+            }
+            if case.accepts_lower() {
+                tables.vpermi2b_hi[0x20 + i] = 9 + i as u8;
+            }
+            i += 1;
+        }
+        if case.accepts_upper() {
+            tables.minlo[4] = 1;
+            tables.maxlo[4] = 6;
+        }
+        if case.accepts_lower() {
+            tables.minlo[6] = 1;
+            tables.maxlo[6] = 6;
+        }
+
+        match case {
+            Case::Lower => {}
+            Case::Upper => tables.first_letter = b'A',
+            Case::Any => tables.fold = 0x20,
+        }
+
+        tables
+    }
+
+    #[inline(always)]
+    fn for_case(case: Case) -> &'static DecodeTables {
+        match case {
+            Case::Lower => &DECODE_TABLES_LOWER,
+            Case::Upper => &DECODE_TABLES_UPPER,
+            Case::Any => &DECODE_TABLES_ANY,
+        }
+    }
+}
+
+static DECODE_TABLES_ANY: DecodeTables = DecodeTables::new(Case::Any);
+static DECODE_TABLES_LOWER: DecodeTables = DecodeTables::new(Case::Lower);
+static DECODE_TABLES_UPPER: DecodeTables = DecodeTables::new(Case::Upper);
+
+/// Decode 128 hex chars → 64 output bytes using vpermi2b + vpmaddubsw.
+/// No deinterleave needed — processes interleaved hex pairs in-place.
+#[inline(always)]
+unsafe fn decode_x86_128(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    tables: &DecodeTables,
+) -> bool {
+    unsafe {
+        let lut_lo =
+            _mm512_load_si512(HEX_DECODE_VPERMI2B_LO.0.as_ptr().cast());
+        let lut_hi = _mm512_load_si512(tables.vpermi2b_hi.as_ptr().cast());
+        let sentinel = _mm512_set1_epi8(0x80u8 as i8);
+        let merge = _mm512_set1_epi16(0x0110);
+
+        let v0 = _mm512_loadu_si512(input.cast());
+        let v1 = _mm512_loadu_si512(input.add(64).cast());
+
+        let mut nib0 = v0;
+        nib0 = _mm512_permutex2var_epi8(lut_lo, nib0, lut_hi);
+        let mut nib1 = v1;
+        nib1 = _mm512_permutex2var_epi8(lut_lo, nib1, lut_hi);
+
+        // vpermi2b ignores bit 7 of the index, so non-ASCII input must be
+        // rejected separately
+        let err0 = _mm512_test_epi8_mask(_mm512_or_si512(nib0, v0), sentinel);
+        let err1 = _mm512_test_epi8_mask(_mm512_or_si512(nib1, v1), sentinel);
+        if (err0 | err1) != 0 {
+            return false;
+        }
+
+        let words0 = _mm512_maddubs_epi16(nib0, merge);
+        let words1 = _mm512_maddubs_epi16(nib1, merge);
+
+        let bytes0 = _mm512_cvtepi16_epi8(words0);
+        let bytes1 = _mm512_cvtepi16_epi8(words1);
+
+        _mm256_storeu_si256(output.cast(), bytes0);
+        _mm256_storeu_si256(output.add(32).cast(), bytes1);
+        true
+    }
+}
+
+/// Decode 64 hex chars → 32 output bytes using vpermi2b + vpmaddubsw.
+#[inline(always)]
+unsafe fn decode_x86_64(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    tables: &DecodeTables,
+) -> bool {
+    unsafe {
+        let lut_lo =
+            _mm512_load_si512(HEX_DECODE_VPERMI2B_LO.0.as_ptr().cast());
+        let lut_hi = _mm512_load_si512(tables.vpermi2b_hi.as_ptr().cast());
+        let sentinel = _mm512_set1_epi8(0x80u8 as i8);
+        let merge = _mm512_set1_epi16(0x0110);
+
+        let v = _mm512_loadu_si512(input.cast());
+        let mut nibbles = v;
+        nibbles = _mm512_permutex2var_epi8(lut_lo, nibbles, lut_hi);
+
+        // vpermi2b ignores bit 7 of the index, so non-ASCII input must be
+        // rejected separately
+        let err = _mm512_test_epi8_mask(_mm512_or_si512(nibbles, v), sentinel);
+        if err != 0 {
+            return false;
+        }
+
+        let words = _mm512_maddubs_epi16(nibbles, merge);
+        let packed = _mm512_cvtepi16_epi8(words);
+
+        _mm256_storeu_si256(output.cast(), packed);
+        true
+    }
+}
+
+// ─── Decode: AVX2/SSSE3 fast paths using pshufb ─────────────────────────
+
+// For AVX2/SSE (no vpermi2b), use high-nibble classification of the
+// original input with offset + range validation via two pshufb LUTs.
+//
+// High nibble of input classifies char type:
+//   3 → '0'-'9' (and ':'-'?', need range check)
+//   4 → 'A'-'F' (and '@','G'-'O', need range check)
+//   6 → 'a'-'f' (and '`','g'-'o', need range check)
+//
+// offset_lut: maps high_nibble → offset to add to get nibble value
+// bound_lut: maps high_nibble → (9 - max_valid_low_nibble) as unsigned,
+//            so that adding it to the low nibble produces > 9 for invalid
+
+/// Maps 32 hex chars to their nibble values using AVX2 pshufb, or returns
+/// `None` if any of them is not a hex digit.
+#[inline(always)]
+unsafe fn hex_nibbles_avx2(
+    v: __m256i,
+    tables: &DecodeTables,
+) -> Option<__m256i> {
+    unsafe {
+        let mask_0f = _mm256_set1_epi8(0x0F);
+
+        let hi_nib = _mm256_and_si256(_mm256_srli_epi16(v, 4), mask_0f);
+        let lo_nib = _mm256_and_si256(v, mask_0f);
+
+        let offset_lut = _mm256_broadcastsi128_si256(_mm_setr_epi8(
+            0, 0, 0, -0x30i8, -0x37i8, 0, -0x57i8, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ));
+
+        // Invalid classes get min=0xFF and max=0, so no lo_nib can satisfy
+        // min <= lo_nib <= max
+        let maxlo_lut = _mm256_broadcastsi128_si256(_mm_loadu_si128(
+            tables.maxlo.as_ptr().cast(),
+        ));
+        let minlo_lut = _mm256_broadcastsi128_si256(_mm_loadu_si128(
+            tables.minlo.as_ptr().cast(),
+        ));
+
+        let offsets = _mm256_shuffle_epi8(offset_lut, hi_nib);
+        let nibbles = _mm256_add_epi8(v, offsets);
+
+        let max_vals = _mm256_shuffle_epi8(maxlo_lut, hi_nib);
+        let min_vals = _mm256_shuffle_epi8(minlo_lut, hi_nib);
+
+        let clamped_hi = _mm256_min_epu8(lo_nib, max_vals);
+        let clamped_lo = _mm256_max_epu8(lo_nib, min_vals);
+        let ok_hi = _mm256_cmpeq_epi8(clamped_hi, lo_nib);
+        let ok_lo = _mm256_cmpeq_epi8(clamped_lo, lo_nib);
+        let ok = _mm256_and_si256(ok_hi, ok_lo);
+        if _mm256_movemask_epi8(ok) != -1i32 {
+            return None;
+        }
+
+        Some(nibbles)
+    }
+}
+
+/// Maps 16 hex chars to their nibble values using SSSE3 pshufb, or returns
+/// `None` if any of them is not a hex digit.
+#[inline(always)]
+unsafe fn hex_nibbles_ssse3(
+    v: __m128i,
+    tables: &DecodeTables,
+) -> Option<__m128i> {
+    unsafe {
+        let mask_0f = _mm_set1_epi8(0x0F);
+
+        let hi_nib = _mm_and_si128(_mm_srli_epi16(v, 4), mask_0f);
+        let lo_nib = _mm_and_si128(v, mask_0f);
+
+        let offset_lut = _mm_setr_epi8(
+            0, 0, 0, -0x30i8, -0x37i8, 0, -0x57i8, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        );
+
+        let maxlo_lut = _mm_loadu_si128(tables.maxlo.as_ptr().cast());
+        let minlo_lut = _mm_loadu_si128(tables.minlo.as_ptr().cast());
+
+        let offsets = _mm_shuffle_epi8(offset_lut, hi_nib);
+        let nibbles = _mm_add_epi8(v, offsets);
+
+        let max_vals = _mm_shuffle_epi8(maxlo_lut, hi_nib);
+        let min_vals = _mm_shuffle_epi8(minlo_lut, hi_nib);
+
+        let clamped_hi = _mm_min_epu8(lo_nib, max_vals);
+        let clamped_lo = _mm_max_epu8(lo_nib, min_vals);
+        let ok_hi = _mm_cmpeq_epi8(clamped_hi, lo_nib);
+        let ok_lo = _mm_cmpeq_epi8(clamped_lo, lo_nib);
+        let ok = _mm_and_si128(ok_hi, ok_lo);
+        if _mm_movemask_epi8(ok) != 0xFFFF {
+            return None;
+        }
+
+        Some(nibbles)
+    }
+}
+
+/// Decode 64 hex chars → 32 output bytes using AVX2 pshufb.
+#[inline(always)]
+unsafe fn decode_avx2_64(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    tables: &DecodeTables,
+) -> bool {
+    unsafe {
+        let v0 = _mm256_loadu_si256(input.cast());
+        let v1 = _mm256_loadu_si256(input.add(32).cast());
+
+        let (Some(nib0), Some(nib1)) =
+            (hex_nibbles_avx2(v0, tables), hex_nibbles_avx2(v1, tables))
+        else {
+            return false;
+        };
+
+        let merge = _mm256_set1_epi16(0x0110);
+        let words0 = _mm256_maddubs_epi16(nib0, merge);
+        let words1 = _mm256_maddubs_epi16(nib1, merge);
+
+        // packus works per 128-bit lane, so the qwords come out as
+        // [w0.lo, w1.lo, w0.hi, w1.hi] and need a cross-lane fix
+        let packed = _mm256_packus_epi16(words0, words1);
+        let packed = _mm256_permute4x64_epi64(packed, 0b11_01_10_00);
+        _mm256_storeu_si256(output.cast(), packed);
+        true
+    }
+}
+
+/// Decode 32 hex chars → 16 output bytes using AVX2 pshufb.
+#[inline(always)]
+unsafe fn decode_x86_32(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    tables: &DecodeTables,
+) -> bool {
+    unsafe {
+        let v = _mm256_loadu_si256(input.cast());
+        let Some(nibbles) = hex_nibbles_avx2(v, tables) else {
+            return false;
+        };
+
+        let merge = _mm256_set1_epi16(0x0110);
+        let words = _mm256_maddubs_epi16(nibbles, merge);
+
+        let packed = _mm_packus_epi16(
+            _mm256_castsi256_si128(words),
+            _mm256_extracti128_si256(words, 1),
+        );
+        _mm_storeu_si128(output.cast(), packed);
+        true
+    }
+}
+
+/// Decode 32 hex chars → 16 output bytes using SSSE3 pshufb.
+#[inline(always)]
+unsafe fn decode_ssse3_32(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    tables: &DecodeTables,
+) -> bool {
+    unsafe {
+        let v0 = _mm_loadu_si128(input.cast());
+        let v1 = _mm_loadu_si128(input.add(16).cast());
+
+        let (Some(nib0), Some(nib1)) =
+            (hex_nibbles_ssse3(v0, tables), hex_nibbles_ssse3(v1, tables))
+        else {
+            return false;
+        };
+
+        let merge = _mm_set1_epi16(0x0110);
+        let words0 = _mm_maddubs_epi16(nib0, merge);
+        let words1 = _mm_maddubs_epi16(nib1, merge);
+
+        let packed = _mm_packus_epi16(words0, words1);
+        _mm_storeu_si128(output.cast(), packed);
+        true
+    }
+}
+
+/// Decode 16 hex chars → 8 output bytes using SSSE3 pshufb.
+#[inline(always)]
+unsafe fn decode_x86_16(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    tables: &DecodeTables,
+) -> bool {
+    unsafe {
+        let v = _mm_loadu_si128(input.cast());
+        let Some(nibbles) = hex_nibbles_ssse3(v, tables) else {
+            return false;
+        };
+
+        let merge = _mm_set1_epi16(0x0110);
+        let words = _mm_maddubs_epi16(nibbles, merge);
+        let packed = _mm_packus_epi16(words, _mm_setzero_si128());
+        _mm_storel_epi64(output.cast(), packed);
+        true
+    }
+}
+
+/// Decode 16 hex chars → 8 output bytes using SSE2 only.
+///
+/// Digits and letters are validated with unsigned range checks
+/// (`min(x, bound) == x`) after rebasing them to zero; when both cases are
+/// accepted, letters are folded to lowercase first by setting bit 5.
+#[inline(always)]
+unsafe fn decode_sse2_16(
+    input: *const u8,
+    output: *mut MaybeUninit<u8>,
+    tables: &DecodeTables,
+) -> bool {
+    unsafe {
+        let v = _mm_loadu_si128(input.cast());
+
+        let digits = _mm_sub_epi8(v, _mm_set1_epi8(b'0' as i8));
+        let letters = _mm_sub_epi8(
+            _mm_or_si128(v, _mm_set1_epi8(tables.fold as i8)),
+            _mm_set1_epi8(tables.first_letter as i8),
+        );
+
+        let is_digit =
+            _mm_cmpeq_epi8(_mm_min_epu8(digits, _mm_set1_epi8(9)), digits);
+        let is_letter =
+            _mm_cmpeq_epi8(_mm_min_epu8(letters, _mm_set1_epi8(5)), letters);
+        if _mm_movemask_epi8(_mm_or_si128(is_digit, is_letter)) != 0xFFFF {
+            return false;
+        }
+
+        let nibbles = _mm_or_si128(
+            _mm_and_si128(is_digit, digits),
+            _mm_andnot_si128(
+                is_digit,
+                _mm_add_epi8(letters, _mm_set1_epi8(10)),
+            ),
+        );
+
+        // Each 16-bit lane holds (lo_nibble << 8) | hi_nibble
+        let hi =
+            _mm_slli_epi16(_mm_and_si128(nibbles, _mm_set1_epi16(0xFF)), 4);
+        let lo = _mm_srli_epi16(nibbles, 8);
+        let words = _mm_or_si128(hi, lo);
+        let packed = _mm_packus_epi16(words, _mm_setzero_si128());
+        _mm_storel_epi64(output.cast(), packed);
+        true
+    }
+}
+
+// ─── Dispatch: runtime CPU feature detection ────────────────────────────
+
+// Each tier gets its own driver compiled with `#[target_feature]`, so the
+// `#[inline(always)]` kernels above are inlined with the right instruction
+// set enabled. Nothing outside a tier's driver may execute its
+// instructions, which lets a single binary run on any x86_64 CPU.
+
+/// Checks for a CPU feature at runtime. Without `std` there is no runtime
+/// detection, so only the features enabled at compile time are used.
+#[cfg(feature = "std")]
+macro_rules! has_cpu_feature {
+    ($feature:tt) => {
+        std::is_x86_feature_detected!($feature)
+    };
+}
+
+#[cfg(not(feature = "std"))]
+macro_rules! has_cpu_feature {
+    ($feature:tt) => {
+        cfg!(target_feature = $feature)
+    };
+}
+
+/// Instruction set tiers with a dedicated kernel set, slowest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Tier {
+    Sse2,
+    Ssse3,
+    Avx2,
+    Avx512Vbmi,
+}
+
+impl Tier {
+    #[cfg(test)]
+    const ALL: [Tier; 4] =
+        [Tier::Sse2, Tier::Ssse3, Tier::Avx2, Tier::Avx512Vbmi];
+
+    fn is_supported(self) -> bool {
+        match self {
+            Tier::Sse2 => true,
+            Tier::Ssse3 => has_cpu_feature!("ssse3"),
+            Tier::Avx2 => has_cpu_feature!("avx2"),
+            Tier::Avx512Vbmi => {
+                has_cpu_feature!("avx2")
+                    && has_cpu_feature!("avx512f")
+                    && has_cpu_feature!("avx512bw")
+                    && has_cpu_feature!("avx512vbmi")
+            }
+        }
+    }
+
+    fn detect() -> Tier {
+        [Tier::Avx512Vbmi, Tier::Avx2, Tier::Ssse3]
+            .into_iter()
+            .find(|tier| tier.is_supported())
+            .unwrap_or(Tier::Sse2)
+    }
+
+    fn kernels(self) -> &'static Kernels {
+        match self {
+            Tier::Sse2 => &SSE2_KERNELS,
+            Tier::Ssse3 => &SSSE3_KERNELS,
+            Tier::Avx2 => &AVX2_KERNELS,
+            Tier::Avx512Vbmi => &AVX512_VBMI_KERNELS,
+        }
+    }
+}
+
+/// Encode and decode drivers for a single [`Tier`].
+///
+/// All functions are only safe to call when the tier is supported by the
+/// running CPU. `encode` expects `dst.len() == 2 * data.len()`,
+/// `encode_grouped` the same plus room for a separator between groups,
+/// `decode` an even-length input and `output.len() == input.len() / 2`,
+/// and `compact` an output at least as long as its input.
+struct Kernels {
+    encode: EncodeFn,
+    encode_grouped: EncodeGroupedFn,
+    decode: DecodeFn,
+    compact: CompactFn,
+}
+
+type EncodeFn = unsafe fn(&[u8], &mut [MaybeUninit<u8>], &[u8; 16]);
+
+type EncodeGroupedFn =
+    unsafe fn(&[u8], &mut [MaybeUninit<u8>], &[u8; 16], usize, u8);
+
+type DecodeFn =
+    unsafe fn(&[u8], &mut [MaybeUninit<u8>], Case) -> Result<(), Error>;
+
+type CompactFn = unsafe fn(&[u8], &mut [u8], &IgnoreSet) -> usize;
+
+static AVX512_VBMI_KERNELS: Kernels = Kernels {
+    encode: encode_avx512_vbmi,
+    encode_grouped: encode_grouped_ssse3,
+    decode: decode_avx512_vbmi,
+    compact: compact_ssse3,
+};
+
+static AVX2_KERNELS: Kernels = Kernels {
+    encode: encode_avx2,
+    encode_grouped: encode_grouped_ssse3,
+    decode: decode_avx2,
+    compact: compact_ssse3,
+};
+
+static SSSE3_KERNELS: Kernels = Kernels {
+    encode: encode_ssse3,
+    encode_grouped: encode_grouped_ssse3,
+    decode: decode_ssse3,
+    compact: compact_ssse3,
+};
+
+static SSE2_KERNELS: Kernels = Kernels {
+    encode: encode_sse2,
+    encode_grouped: encode_grouped_sse2,
+    decode: decode_sse2,
+    compact: compact_scalar,
+};
+
+/// Kernel table for the detected tier, null until first use.
+static KERNELS: AtomicPtr<Kernels> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Returns the kernel table of the best tier supported by the running CPU,
+/// detecting it on first use.
+#[inline(always)]
+fn kernels() -> &'static Kernels {
+    let cached = KERNELS.load(Ordering::Relaxed);
+    if !cached.is_null() {
+        // SAFETY: only ever set to one of the `'static` tables above
+        return unsafe { &*cached };
+    }
+
+    let kernels = Tier::detect().kernels();
+    KERNELS.store(core::ptr::from_ref(kernels).cast_mut(), Ordering::Relaxed);
+    kernels
+}
+
+#[target_feature(enable = "avx2,avx512f,avx512bw,avx512vbmi")]
+unsafe fn encode_avx512_vbmi(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+) {
+    let mut pos = 0;
+
+    unsafe {
+        let input = data.as_ptr();
+        let output = dst.as_mut_ptr();
+
+        while pos + 64 <= data.len() {
+            encode_simd_64(input.add(pos), output.add(pos * 2), lut);
+            pos += 64;
+        }
+
+        while pos + 32 <= data.len() {
+            encode_simd_32(input.add(pos), output.add(pos * 2), lut);
+            pos += 32;
+        }
+
+        if pos < data.len() {
+            if data.len() >= 32 {
+                let start = data.len() - 32;
+                encode_simd_32(input.add(start), output.add(start * 2), lut);
+            } else if data.len() >= 16 {
+                encode_simd_16(input, output, lut);
+                if data.len() > 16 {
+                    let start = data.len() - 16;
+                    encode_simd_16(
+                        input.add(start),
+                        output.add(start * 2),
+                        lut,
+                    );
+                }
+            } else {
+                encode_scalar(&data[pos..], &mut dst[pos * 2..], lut);
+            }
+        }
+    }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn encode_avx2(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+) {
+    let mut pos = 0;
+
+    unsafe {
+        let input = data.as_ptr();
+        let output = dst.as_mut_ptr();
+
+        while pos + 32 <= data.len() {
+            encode_simd_32(input.add(pos), output.add(pos * 2), lut);
+            pos += 32;
+        }
+
+        if pos < data.len() {
+            if data.len() >= 32 {
+                let start = data.len() - 32;
+                encode_simd_32(input.add(start), output.add(start * 2), lut);
+            } else if data.len() >= 16 {
+                encode_simd_16(input, output, lut);
+                if data.len() > 16 {
+                    let start = data.len() - 16;
+                    encode_simd_16(
+                        input.add(start),
+                        output.add(start * 2),
+                        lut,
+                    );
+                }
+            } else {
+                encode_scalar(&data[pos..], &mut dst[pos * 2..], lut);
+            }
+        }
+    }
+}
+
+#[target_feature(enable = "ssse3")]
+unsafe fn encode_ssse3(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+) {
+    let mut pos = 0;
+
+    unsafe {
+        let input = data.as_ptr();
+        let output = dst.as_mut_ptr();
+
+        while pos + 16 <= data.len() {
+            encode_simd_16(input.add(pos), output.add(pos * 2), lut);
+            pos += 16;
+        }
+
+        if pos < data.len() {
+            if data.len() >= 16 {
+                let start = data.len() - 16;
+                encode_simd_16(input.add(start), output.add(start * 2), lut);
+            } else {
+                encode_scalar(&data[pos..], &mut dst[pos * 2..], lut);
+            }
+        }
+    }
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn encode_sse2(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+) {
+    let mut pos = 0;
+
+    unsafe {
+        let input = data.as_ptr();
+        let output = dst.as_mut_ptr();
+
+        while pos + 16 <= data.len() {
+            encode_sse2_16(input.add(pos), output.add(pos * 2), lut);
+            pos += 16;
+        }
+
+        if pos < data.len() {
+            if data.len() >= 16 {
+                let start = data.len() - 16;
+                encode_sse2_16(input.add(start), output.add(start * 2), lut);
+            } else {
+                encode_scalar(&data[pos..], &mut dst[pos * 2..], lut);
+            }
+        }
+    }
+}
+
+/// Shared by the AVX tiers, which have nothing wider to offer for the
+/// 48-byte output blocks.
+#[target_feature(enable = "ssse3")]
+unsafe fn encode_grouped_ssse3(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+    group: usize,
+    separator: u8,
+) {
+    let mut pos = 0;
+    let mut out = 0;
+
+    if let Some(shuffle) = GroupShuffle::for_group(group) {
+        unsafe {
+            let input = data.as_ptr();
+            let output = dst.as_mut_ptr();
+            let separator = _mm_set1_epi8(separator as i8);
+
+            // The 48-byte stores may run past the block, but only into
+            // output that later blocks or the tail overwrite
+            while pos + 16 <= data.len() && out + 48 <= dst.len() {
+                encode_grouped_simd_16(
+                    input.add(pos),
+                    output.add(out),
+                    lut,
+                    shuffle,
+                    separator,
+                );
+                pos += 16;
+                out += shuffle.len;
+            }
+        }
+    }
+
+    encode_grouped_with(
+        &data[pos..],
+        &mut dst[out..],
+        lut,
+        group,
+        separator,
+        |data, dst, lut| unsafe { encode_ssse3(data, dst, lut) },
+    );
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn encode_grouped_sse2(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+    group: usize,
+    separator: u8,
+) {
+    encode_grouped_with(
+        data,
+        dst,
+        lut,
+        group,
+        separator,
+        |data, dst, lut| unsafe { encode_sse2(data, dst, lut) },
+    );
+}
+
+/// Shared by the AVX tiers: the shuffle table compacts 8 bytes at a time,
+/// so wider vectors would only add more table lookups.
+#[target_feature(enable = "ssse3")]
+unsafe fn compact_ssse3(
+    input: &[u8],
+    output: &mut [u8],
+    ignore: &IgnoreSet,
+) -> usize {
+    let mut pos = 0;
+    let mut len = 0;
+
+    unsafe {
+        let rows = _mm_loadu_si128(ignore.rows.as_ptr().cast());
+
+        // Every store ends at most 16 bytes past `len <= pos`, so within
+        // the `output.len() >= input.len()` bytes
+        while pos + 16 <= input.len() {
+            len += compact_ssse3_16(
+                input.as_ptr().add(pos),
+                output.as_mut_ptr().add(len),
+                rows,
+            );
+            pos += 16;
+        }
+    }
+
+    len + compact_scalar(&input[pos..], &mut output[len..], ignore)
+}
+
+#[target_feature(enable = "avx2,avx512f,avx512bw,avx512vbmi")]
+unsafe fn decode_avx512_vbmi(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    let tables = DecodeTables::for_case(case);
+    let n = input.len();
+    let mut pos = 0;
+    let mut out_pos = 0;
+
+    unsafe {
+        // Main loop: 128 hex chars → 64 output bytes per iteration
+        while pos + 128 <= n {
+            if !decode_x86_128(
+                input.as_ptr().add(pos),
+                output.as_mut_ptr().add(out_pos),
+                tables,
+            ) {
+                return Err(invalid_hex_char_error(input, pos, case));
+            }
+            pos += 128;
+            out_pos += 64;
+        }
+
+        // Remainder: 64 hex chars
+        if pos + 64 <= n {
+            if !decode_x86_64(
+                input.as_ptr().add(pos),
+                output.as_mut_ptr().add(out_pos),
+                tables,
+            ) {
+                return Err(invalid_hex_char_error(input, pos, case));
+            }
+            pos += 64;
+            out_pos += 32;
+        }
+    }
+
+    // Handle remainder with overlapping SIMD reads
+    if pos < n {
+        if n >= 128 {
+            // Re-decode last 128 hex bytes via overlapping
+            let start = n - 128;
+            let out_start = start / 2;
+            if !unsafe {
+                decode_x86_128(
+                    input.as_ptr().add(start),
+                    output.as_mut_ptr().add(out_start),
+                    tables,
+                )
+            } {
+                return Err(invalid_hex_char_error(input, start, case));
+            }
+        } else if n >= 64 {
+            // Re-decode last 64 hex bytes via overlapping
+            let start = n - 64;
+            let out_start = start / 2;
+            if !unsafe {
+                decode_x86_64(
+                    input.as_ptr().add(start),
+                    output.as_mut_ptr().add(out_start),
+                    tables,
+                )
+            } {
+                return Err(invalid_hex_char_error(input, start, case));
+            }
+        } else if n >= 32 {
+            // Use AVX2 32-byte path with overlapping
+            unsafe {
+                // First 32 hex bytes
+                if pos + 32 <= n {
+                    if !decode_x86_32(
+                        input.as_ptr().add(pos),
+                        output.as_mut_ptr().add(out_pos),
+                        tables,
+                    ) {
+                        return Err(invalid_hex_char_error(input, pos, case));
+                    }
+                    pos += 32;
+                }
+                // Overlapping last 32 hex bytes if remainder
+                if pos < n {
+                    let start = n - 32;
+                    let out_start = start / 2;
+                    if !decode_x86_32(
+                        input.as_ptr().add(start),
+                        output.as_mut_ptr().add(out_start),
+                        tables,
+                    ) {
+                        return Err(invalid_hex_char_error(input, start, case));
+                    }
+                }
+            }
+        } else if n >= 16 {
+            // Use SSE 16-byte path with overlapping
+            unsafe {
+                if !decode_x86_16(
+                    input.as_ptr().add(pos),
+                    output.as_mut_ptr().add(out_pos),
+                    tables,
+                ) {
+                    return Err(invalid_hex_char_error(input, pos, case));
+                }
+                if n > 16 {
+                    let start = n - 16;
+                    let out_start = start / 2;
+                    if !decode_x86_16(
+                        input.as_ptr().add(start),
+                        output.as_mut_ptr().add(out_start),
+                        tables,
+                    ) {
+                        return Err(invalid_hex_char_error(input, start, case));
+                    }
+                }
+            }
+        } else {
+            // < 16 hex bytes: scalar LUT fallback
+            let remaining = n - pos;
+            decode_remainder_lut(input, output, pos, out_pos, remaining, case)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn decode_avx2(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    let tables = DecodeTables::for_case(case);
+    let n = input.len();
+    let mut pos = 0;
+    let mut out_pos = 0;
+
+    unsafe {
+        // Main loop: 64 hex chars → 32 output bytes per iteration
+        while pos + 64 <= n {
+            if !decode_avx2_64(
+                input.as_ptr().add(pos),
+                output.as_mut_ptr().add(out_pos),
+                tables,
+            ) {
+                return Err(invalid_hex_char_error(input, pos, case));
+            }
+            pos += 64;
+            out_pos += 32;
+        }
+
+        if pos < n {
+            if n >= 64 {
+                // Re-decode last 64 hex bytes via overlapping
+                let start = n - 64;
+                if !decode_avx2_64(
+                    input.as_ptr().add(start),
+                    output.as_mut_ptr().add(start / 2),
+                    tables,
+                ) {
+                    return Err(invalid_hex_char_error(input, start, case));
+                }
+                pos = n;
+            } else if n >= 32 {
+                if !decode_x86_32(input.as_ptr(), output.as_mut_ptr(), tables) {
+                    return Err(invalid_hex_char_error(input, 0, case));
+                }
+                if n > 32 {
+                    let start = n - 32;
+                    if !decode_x86_32(
+                        input.as_ptr().add(start),
+                        output.as_mut_ptr().add(start / 2),
+                        tables,
+                    ) {
+                        return Err(invalid_hex_char_error(input, start, case));
+                    }
+                }
+                pos = n;
+            } else if n >= 16 {
+                if !decode_x86_16(input.as_ptr(), output.as_mut_ptr(), tables) {
+                    return Err(invalid_hex_char_error(input, 0, case));
+                }
+                if n > 16 {
+                    let start = n - 16;
+                    if !decode_x86_16(
+                        input.as_ptr().add(start),
+                        output.as_mut_ptr().add(start / 2),
+                        tables,
+                    ) {
+                        return Err(invalid_hex_char_error(input, start, case));
+                    }
+                }
+                pos = n;
+            }
+        }
+    }
+
+    if pos < n {
+        decode_remainder_lut(input, output, pos, out_pos, n - pos, case)?;
+    }
+
+    Ok(())
+}
+
+#[target_feature(enable = "ssse3")]
+unsafe fn decode_ssse3(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    let tables = DecodeTables::for_case(case);
+    let n = input.len();
+    let mut pos = 0;
+    let mut out_pos = 0;
+
+    unsafe {
+        // Main loop: 32 hex chars → 16 output bytes per iteration
+        while pos + 32 <= n {
+            if !decode_ssse3_32(
+                input.as_ptr().add(pos),
+                output.as_mut_ptr().add(out_pos),
+                tables,
+            ) {
+                return Err(invalid_hex_char_error(input, pos, case));
+            }
+            pos += 32;
+            out_pos += 16;
+        }
+
+        if pos < n {
+            if n >= 32 {
+                // Re-decode last 32 hex bytes via overlapping
+                let start = n - 32;
+                if !decode_ssse3_32(
+                    input.as_ptr().add(start),
+                    output.as_mut_ptr().add(start / 2),
+                    tables,
+                ) {
+                    return Err(invalid_hex_char_error(input, start, case));
+                }
+                pos = n;
+            } else if n >= 16 {
+                if !decode_x86_16(input.as_ptr(), output.as_mut_ptr(), tables) {
+                    return Err(invalid_hex_char_error(input, 0, case));
+                }
+                if n > 16 {
+                    let start = n - 16;
+                    if !decode_x86_16(
+                        input.as_ptr().add(start),
+                        output.as_mut_ptr().add(start / 2),
+                        tables,
+                    ) {
+                        return Err(invalid_hex_char_error(input, start, case));
+                    }
+                }
+                pos = n;
+            }
+        }
+    }
+
+    if pos < n {
+        decode_remainder_lut(input, output, pos, out_pos, n - pos, case)?;
+    }
+
+    Ok(())
+}
+
+#[target_feature(enable = "sse2")]
+unsafe fn decode_sse2(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    let tables = DecodeTables::for_case(case);
+    let n = input.len();
+    let mut pos = 0;
+    let mut out_pos = 0;
+
+    unsafe {
+        while pos + 16 <= n {
+            if !decode_sse2_16(
+                input.as_ptr().add(pos),
+                output.as_mut_ptr().add(out_pos),
+                tables,
+            ) {
+                return Err(invalid_hex_char_error(input, pos, case));
+            }
+            pos += 16;
+            out_pos += 8;
+        }
+
+        // Re-decode the last 16 hex bytes via overlapping
+        if pos < n && n >= 16 {
+            let start = n - 16;
+            if !decode_sse2_16(
+                input.as_ptr().add(start),
+                output.as_mut_ptr().add(start / 2),
+                tables,
+            ) {
+                return Err(invalid_hex_char_error(input, start, case));
+            }
+            pos = n;
+        }
+    }
+
+    if pos < n {
+        decode_remainder_lut(input, output, pos, out_pos, n - pos, case)?;
+    }
+
+    Ok(())
+}
+
+// ─── Entry points ───────────────────────────────────────────────────────
+
+#[inline(always)]
+pub(crate) fn encode(data: &[u8], dst: &mut [MaybeUninit<u8>], lut: &[u8; 16]) {
+    // SAFETY: `kernels()` only hands out kernels the running CPU supports
+    unsafe { (kernels().encode)(data, dst, lut) }
+}
+
+#[inline(always)]
+pub(crate) fn encode_grouped(
+    data: &[u8],
+    dst: &mut [MaybeUninit<u8>],
+    lut: &[u8; 16],
+    group: usize,
+    separator: u8,
+) {
+    // SAFETY: `kernels()` only hands out kernels the running CPU supports
+    unsafe { (kernels().encode_grouped)(data, dst, lut, group, separator) }
+}
+
+#[inline(always)]
+pub(crate) fn compact(
+    input: &[u8],
+    output: &mut [u8],
+    ignore: &IgnoreSet,
+) -> usize {
+    // SAFETY: `kernels()` only hands out kernels the running CPU supports
+    unsafe { (kernels().compact)(input, output, ignore) }
+}
+
+#[inline(always)]
+pub(crate) fn decode(
+    input: &[u8],
+    output: &mut [MaybeUninit<u8>],
+    case: Case,
+) -> Result<(), Error> {
+    // SAFETY: `kernels()` only hands out kernels the running CPU supports
+    unsafe { (kernels().decode)(input, output, case) }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::Tier;
+    use crate::{
+        Buf,
+        Case,
+        Error,
+        HEX_ENCODE_LUT,
+        HEX_ENCODE_LUT_UPPER,
+        IgnoreSet,
+        decode_remainder_lut,
+        encode_grouped_reference,
+    };
+
+    fn supported_tiers() -> impl Iterator<Item = Tier> {
+        Tier::ALL.into_iter().filter(|tier| tier.is_supported())
+    }
+
+    fn encode_with_tier(tier: Tier, input: &[u8], lut: &[u8; 16]) -> String {
+        let mut output = vec![0u8; input.len() * 2];
+        unsafe { (tier.kernels().encode)(input, output.dst(), lut) };
+        String::from_utf8(output).unwrap()
+    }
+
+    fn decode_with_tier(
+        tier: Tier,
+        input: &[u8],
+        case: Case,
+    ) -> Result<Vec<u8>, Error> {
+        let mut output = vec![0u8; input.len() / 2];
+        unsafe { (tier.kernels().decode)(input, output.dst(), case)? };
+        Ok(output)
+    }
+
+    fn decode_reference(input: &[u8], case: Case) -> Result<Vec<u8>, Error> {
+        let mut output = vec![0u8; input.len() / 2];
+        let dst = unsafe { output.dst() };
+        decode_remainder_lut(input, dst, 0, 0, input.len(), case)?;
+        Ok(output)
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_encode_parity(input: Vec<u8>) {
+        for tier in supported_tiers() {
+            prop_assert_eq!(
+                encode_with_tier(tier, &input, &HEX_ENCODE_LUT),
+                hex::encode(&input),
+                "tier {:?}",
+                tier
+            );
+        }
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_encode_upper_parity(input: Vec<u8>) {
+        for tier in supported_tiers() {
+            prop_assert_eq!(
+                encode_with_tier(tier, &input, &HEX_ENCODE_LUT_UPPER),
+                hex::encode_upper(&input),
+                "tier {:?}",
+                tier
+            );
+        }
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_compact(
+        #[strategy(proptest::collection::vec(
+            proptest::sample::select(&b"0123456789abcdef :-_\t\n\x80\xff"[..]),
+            0..200,
+        ))]
+        input: Vec<u8>,
+    ) {
+        let ignore = IgnoreSet::default();
+        let expected: Vec<u8> = input
+            .iter()
+            .copied()
+            .filter(|&byte| !ignore.contains(byte))
+            .collect();
+
+        for tier in supported_tiers() {
+            let mut output = vec![0u8; input.len()];
+            let len = unsafe {
+                (tier.kernels().compact)(&input, &mut output, &ignore)
+            };
+            prop_assert_eq!(&output[..len], &expected[..], "tier {:?}", tier);
+        }
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_encode_grouped_parity(
+        #[strategy(proptest::collection::vec(any::<u8>(), 0..200))] input: Vec<
+            u8,
+        >,
+        #[strategy(1usize..20)] group: usize,
+        separator: u8,
+        upper: bool,
+    ) {
+        let lut = if upper {
+            &HEX_ENCODE_LUT_UPPER
+        } else {
+            &HEX_ENCODE_LUT
+        };
+        let expected = encode_grouped_reference(&input, lut, group, separator);
+        for tier in supported_tiers() {
+            let mut output = vec![0u8; expected.len()];
+            let grouped = tier.kernels().encode_grouped;
+            unsafe { grouped(&input, output.dst(), lut, group, separator) };
+            prop_assert_eq!(&output, &expected, "tier {:?}", tier);
+        }
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_decode_roundtrip(input: Vec<u8>) {
+        let encoded = hex::encode_upper(&input);
+        for tier in supported_tiers() {
+            prop_assert_eq!(
+                decode_with_tier(tier, encoded.as_bytes(), Case::Any).ok(),
+                Some(input.clone()),
+                "tier {:?}",
+                tier
+            );
+        }
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_decode_parity(input: Vec<u8>) {
+        let input = &input[..input.len() & !1];
+        for tier in supported_tiers() {
+            prop_assert_eq!(
+                decode_with_tier(tier, input, Case::Any).ok(),
+                hex::decode(input).ok(),
+                "tier {:?}",
+                tier
+            );
+        }
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_reports_first_invalid_char(
+        input: Vec<u8>,
+        #[strategy(proptest::collection::vec(any::<(usize, u8)>(), 1..4))]
+        corruptions: Vec<(usize, u8)>,
+    ) {
+        let mut encoded = hex::encode(&input).into_bytes();
+        for (at, byte) in corruptions {
+            if !encoded.is_empty() && !byte.is_ascii_hexdigit() {
+                let at = at % encoded.len();
+                encoded[at] = byte;
+            }
+        }
+
+        let expected =
+            match encoded.iter().position(|byte| !byte.is_ascii_hexdigit()) {
+                Some(index) => Err(Error::InvalidChar {
+                    byte: encoded[index],
+                    index,
+                }),
+                None => Ok(input),
+            };
+
+        for tier in supported_tiers() {
+            prop_assert_eq!(
+                decode_with_tier(tier, &encoded, Case::Any),
+                expected.clone(),
+                "tier {:?}",
+                tier
+            );
+        }
+    }
+
+    #[test_strategy::proptest(cases = 2000)]
+    fn test_forced_tier_strict_case(
+        input: Vec<u8>,
+        upper: bool,
+        flip: Option<usize>,
+    ) {
+        let mut encoded = match upper {
+            false => hex::encode(&input).into_bytes(),
+            true => hex::encode_upper(&input).into_bytes(),
+        };
+        if let Some(at) = flip.filter(|_| !encoded.is_empty()) {
+            let at = at % encoded.len();
+            encoded[at] ^= 0x20;
+        }
+
+        for case in [Case::Lower, Case::Upper, Case::Any] {
+            for tier in supported_tiers() {
+                prop_assert_eq!(
+                    decode_with_tier(tier, &encoded, case),
+                    decode_reference(&encoded, case),
+                    "tier {:?}, case {:?}",
+                    tier,
+                    case
+                );
+            }
+        }
+    }
+
+    /// Every tier must reject each non-hex byte at every position of every
+    /// block size it handles, including the overlapping tails.
+    #[test]
+    fn test_forced_tier_rejects_invalid_chars() {
+        for case in [Case::Lower, Case::Upper, Case::Any] {
+            let lut = case.decode_lut();
+            let filler = if case == Case::Upper { b'A' } else { b'a' };
+            let invalid = (0..=255u8).filter(|&b| lut[b as usize] == 255);
+
+            for ch in invalid {
+                for len in [2, 16, 18, 32, 34, 48, 64, 66, 96, 128, 130, 258] {
+                    for at in [0, len / 2 - 1, len - 1] {
+                        let mut input = vec![filler; len];
+                        input[at] = ch;
+
+                        for tier in supported_tiers() {
+                            assert_eq!(
+                                decode_with_tier(tier, &input, case),
+                                Err(Error::InvalidChar {
+                                    byte: ch,
+                                    index: at
+                                }),
+                                "tier {tier:?}, {case:?}, 0x{ch:02X} at \
+                                 {at}/{len}",
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}